@@ -0,0 +1,135 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use optim::{Budget, Mcts, MctsConfig, Rollout, Tree};
+use rand::prelude::ThreadRng;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+/// Pick a digit at each turn, the score is the digit if it is even
+#[derive(Debug, Clone)]
+struct Digits {
+    depth: usize,
+}
+
+impl Rollout<u8> for Digits {
+    type Score = i64;
+
+    fn fill(&self, actions: &mut Vec<u8>) {
+        if self.depth > 0 {
+            actions.extend(0..10)
+        }
+    }
+
+    fn update(&mut self, action: u8) -> i64 {
+        self.depth -= 1;
+        if action.is_multiple_of(2) {
+            action as i64
+        } else {
+            0
+        }
+    }
+}
+
+impl Mcts<u8> for Digits {}
+
+/// Previous tree: Rc nodes with a HashMap of children, recursive rollout
+///
+/// Same tree policy as `Mcts` with the default config: a random untried action
+/// while there is one, then UCB1, every node of the trajectory is expanded.
+mod rc {
+    use super::*;
+    use rand::Rng;
+
+    #[derive(Default)]
+    pub struct Node {
+        // Never read, kept for the same memory footprint
+        #[allow(dead_code)]
+        parent: Weak<Node>,
+        children: RefCell<HashMap<u8, Rc<Node>>>,
+        visit: RefCell<u64>,
+        score: RefCell<f64>,
+    }
+
+    impl Node {
+        fn ucb1(&self, parent_visit: u64) -> f64 {
+            let visit = *self.visit.borrow() as f64;
+            *self.score.borrow() / visit
+                + std::f64::consts::SQRT_2 * ((parent_visit as f64).ln() / visit).sqrt()
+        }
+    }
+
+    /// Return the score from `node` to the end of the game
+    pub fn rollout(
+        game: &mut Digits,
+        rng: &mut ThreadRng,
+        node: &Rc<Node>,
+        actions: &mut Vec<u8>,
+    ) -> f64 {
+        actions.clear();
+        game.fill(actions);
+        *node.visit.borrow_mut() += 1;
+        let parent_visit = *node.visit.borrow();
+
+        let child = {
+            let mut children = node.children.borrow_mut();
+            actions.retain(|action| !children.contains_key(action));
+            if !actions.is_empty() {
+                let action = actions[rng.gen_range(0..actions.len())];
+                let child = Rc::new(Node {
+                    parent: Rc::downgrade(node),
+                    ..Default::default()
+                });
+                children.insert(action, child.clone());
+                Some((action, child))
+            } else {
+                children
+                    .iter()
+                    .max_by(|a, b| a.1.ucb1(parent_visit).total_cmp(&b.1.ucb1(parent_visit)))
+                    .map(|(&action, child)| (action, child.clone()))
+            }
+        };
+
+        let Some((action, child)) = child else {
+            return 0.;
+        };
+        let score = game.update(action) as f64 + rollout(game, rng, &child, actions);
+        *child.score.borrow_mut() += score;
+        score
+    }
+}
+
+fn bench_mcts(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let mut actions = Vec::new();
+    let mut group = c.benchmark_group("mcts");
+    let config: MctsConfig = MctsConfig::default();
+
+    for iterations in [100, 1_000, 10_000].iter() {
+        let game = Digits { depth: 20 };
+        let budget = Budget::iterations(*iterations);
+
+        group.throughput(Throughput::Elements(*iterations as u64));
+        group.bench_with_input(BenchmarkId::new("rc", iterations), iterations, |b, _| {
+            b.iter(|| {
+                let node = Rc::new(rc::Node::default());
+                for _ in 0..*iterations {
+                    rc::rollout(&mut game.clone(), &mut rng, &node, &mut actions);
+                }
+                node
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("arena", iterations), iterations, |b, _| {
+            b.iter(|| {
+                let mut tree = Tree::default();
+                game.clone()
+                    .mcts(&mut rng, &budget, &config, &mut tree, &mut actions)
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_mcts);
+criterion_main!(benches);
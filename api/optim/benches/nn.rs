@@ -0,0 +1,46 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use optim::{Activation, Backend, NN};
+
+/// Policy network of a 9x9 board, as in the MCTS bots
+fn network(backend: Backend) -> NN<81, 81> {
+    NN::<81, 81>::builder()
+        .normalize()
+        .dense(256, Activation::Relu)
+        .dense(128, Activation::Relu)
+        .output(Activation::Softmax)
+        .with_backend(backend)
+}
+
+fn bench_nn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nn");
+    let input = [(); 81].map(|_| rand::random::<f32>());
+    let parameters = 81 * 256 + 256 * 128 + 128 * 81;
+
+    let mut backends = vec![Backend::Scalar];
+    if Backend::detect() != Backend::Scalar {
+        backends.push(Backend::Sse);
+    }
+    if Backend::detect() == Backend::Avx {
+        backends.push(Backend::Avx);
+    }
+
+    group.throughput(Throughput::Elements(parameters as u64));
+    for backend in backends {
+        let name = format!("{backend:?}");
+        let mut nn = network(backend);
+        group.bench_with_input(BenchmarkId::new("predict", &name), &input, |b, &input| {
+            b.iter(|| nn.predict(input));
+        });
+        group.bench_with_input(BenchmarkId::new("backprop", &name), &input, |b, &input| {
+            b.iter(|| {
+                let output = nn.predict(input);
+                nn.backprop(output);
+                nn.layers.iter_mut().for_each(|layer| layer.zero_grad());
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_nn);
+criterion_main!(benches);
@@ -0,0 +1,489 @@
+use crate::{
+    backward_layers, forward_layers, outcome, step_layers, Activation, Agent, Budget, BudgetStats,
+    Dense, Game, Loss, OptimizerConfig, OptimizerState, Puct, Selection, Status, Tree,
+};
+use rand::prelude::{Distribution, SliceRandom, ThreadRng};
+use rand_distr::Gamma;
+use vector::VectorOp;
+
+/// Shared trunk of dense layers with a policy head over the A actions and a value head
+///
+/// The policy is a softmax, the value is in [-1, 1] for the player to move.
+#[derive(Debug, Clone)]
+pub struct PolicyValue<const IN: usize, const A: usize> {
+    pub trunk: Vec<Dense>,
+    pub policy: Dense,
+    pub value: Dense,
+    pub optimizer: OptimizerConfig,
+    state: OptimizerState,
+}
+
+impl<const IN: usize, const A: usize> PolicyValue<IN, A> {
+    /// ReLU trunk of the given widths
+    pub fn new(trunk: &[usize]) -> Self {
+        let mut rng = rand::thread_rng();
+        let mut inputs = IN;
+        let trunk = trunk
+            .iter()
+            .map(|&width| {
+                let layer = Dense::new(&mut rng, inputs, width, Activation::Relu);
+                inputs = width;
+                layer
+            })
+            .collect();
+
+        Self {
+            trunk,
+            policy: Dense::new(&mut rng, inputs, A, Activation::Softmax),
+            value: Dense::new(&mut rng, inputs, 1, Activation::Tanh),
+            optimizer: OptimizerConfig::default(),
+            state: OptimizerState::default(),
+        }
+    }
+
+    pub fn with_optimizer(mut self, optimizer: OptimizerConfig) -> Self {
+        self.optimizer = optimizer;
+        self.state.reset();
+        self
+    }
+
+    /// Return the policy and the value
+    pub fn predict(&mut self, input: [f32; IN]) -> ([f32; A], f32) {
+        let features = forward_layers(&mut self.trunk, &input);
+        let mut policy = [0.; A];
+        policy.copy_from_slice(self.policy.forward(features));
+        let value = self.value.forward(features)[0];
+        (policy, value)
+    }
+
+    /// Accumulate the gradients of the last forward pass, return its loss
+    /// Cross-entropy of the policy plus squared error of the value
+    pub fn backprop(&mut self, policy: &[f32; A], value: f32) -> f32 {
+        let mut policy_grad = [0.; A];
+        let mut value_grad = [0.];
+        let loss = Loss::CrossEntropy.eval(self.policy.output(), policy, &mut policy_grad)
+            + Loss::Mse.eval(self.value.output(), &[value], &mut value_grad);
+
+        let (mut grad, mut grad_input) = (Vec::new(), Vec::new());
+        self.policy.backward(&mut policy_grad, &mut grad);
+        self.value.backward(&mut value_grad, &mut grad_input);
+        VectorOp::add_assign(&mut grad, &grad_input);
+        backward_layers(&mut self.trunk, &mut grad, &mut grad_input);
+        loss
+    }
+
+    /// Update every layer with the accumulated gradients, which are reset
+    pub fn step(&mut self) {
+        let mut layers = self
+            .trunk
+            .iter_mut()
+            .chain([&mut self.policy, &mut self.value])
+            .collect::<Vec<_>>();
+        step_layers(&self.optimizer, &mut self.state, &mut layers);
+    }
+
+    /// One pass over the samples in shuffled mini-batches, return the mean loss
+    pub fn learn(
+        &mut self,
+        rng: &mut ThreadRng,
+        samples: &[Sample<IN, A>],
+        batch_size: usize,
+    ) -> f32 {
+        let mut order = (0..samples.len()).collect::<Vec<_>>();
+        order.shuffle(rng);
+        let mut total = 0.;
+
+        for batch in order.chunks(batch_size.max(1)) {
+            for &i in batch {
+                let sample = &samples[i];
+                self.predict(sample.input);
+                total += self.backprop(&sample.policy, sample.value);
+            }
+            // Mean over the batch
+            self.trunk
+                .iter_mut()
+                .chain([&mut self.policy, &mut self.value])
+                .for_each(|layer| {
+                    let c = 1. / batch.len() as f32;
+                    layer.parameters().into_iter().for_each(|(_, grads)| {
+                        VectorOp::mul_assign_with(grads, c);
+                    });
+                });
+            self.step();
+        }
+
+        total / samples.len().max(1) as f32
+    }
+}
+
+/// Position of a self-play game
+/// - policy: Visit distribution of the search
+/// - value: Outcome of the game for the player to move, in [-1, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample<const IN: usize, const A: usize> {
+    pub input: [f32; IN],
+    pub policy: [f32; A],
+    pub value: f32,
+}
+
+/// - puct: Tree policy, unvisited children are worth `fpu`
+/// - budget: Search of each move
+/// - max_nodes: Size of the tree
+/// - noise: Dirichlet (alpha, weight) mixed into the priors of the root
+/// - temperature_moves: Self-play moves sampled from the visit distribution, then the most
+///   visited is played
+/// - batch_size: Mini-batches of the training after each game
+/// - capacity: Samples kept for training, the oldest are dropped
+#[derive(Debug, Clone)]
+pub struct AlphaZeroConfig {
+    pub puct: Puct,
+    pub fpu: f64,
+    pub budget: Budget,
+    pub max_nodes: usize,
+    pub noise: Option<(f64, f64)>,
+    pub temperature_moves: usize,
+    pub batch_size: usize,
+    pub capacity: usize,
+}
+
+impl Default for AlphaZeroConfig {
+    fn default() -> Self {
+        Self {
+            puct: Puct { c: 1.5 },
+            fpu: 0.5,
+            budget: Budget::iterations(100),
+            max_nodes: 100_000,
+            noise: Some((0.3, 0.25)),
+            temperature_moves: 8,
+            batch_size: 32,
+            capacity: 10_000,
+        }
+    }
+}
+
+/// Leaf reached by `AlphaZero::az_rollout`
+pub enum Leaf {
+    Terminal(Status),
+    /// Player to move and the value of the network, in [0, 1]
+    Value(usize, f64),
+}
+
+impl Leaf {
+    /// Score of a player, in [0, 1] like `outcome`
+    pub fn score(&self, player: usize) -> f64 {
+        match *self {
+            Leaf::Terminal(status) => outcome(status, player),
+            Leaf::Value(p, value) if p == player => value,
+            Leaf::Value(_, value) => 1. - value,
+        }
+    }
+}
+
+/// MCTS guided by a `PolicyValue` network for two-player games
+///
+/// Leaves are expanded with the policy as priors and evaluated by the value head instead
+/// of a rollout. The actions are the indices of the policy.
+pub trait AlphaZero: Game {
+    /// Descend with PUCT to a leaf, expand and evaluate it
+    /// Return the leaf and the number of updates
+    fn az_rollout<const IN: usize, const A: usize>(
+        &mut self,
+        config: &AlphaZeroConfig,
+        net: &mut PolicyValue<IN, A>,
+        tree: &mut Tree<usize>,
+        actions: &mut Vec<usize>,
+    ) -> (Leaf, usize) {
+        let mut path = std::mem::take(&mut tree.moves);
+        path.clear();
+        let mut node = 0;
+
+        let leaf = loop {
+            self.fill(actions);
+            tree[node].visit += 1;
+            if actions.is_empty() {
+                break Leaf::Terminal(self.status());
+            }
+
+            if tree[node].first_child.is_none() {
+                let (policy, value) = net.predict(self.input());
+                let total = actions.iter().map(|&a| policy[a]).sum::<f32>();
+                for &action in actions.iter() {
+                    let prior = if total > 0. {
+                        policy[action] / total
+                    } else {
+                        1. / actions.len() as f32
+                    };
+                    tree.add_child(node, action, prior as f64);
+                }
+                let player = self.turn() % Self::PLAYERS;
+                break Leaf::Value(player, (value as f64 + 1.) / 2.);
+            }
+
+            let parent_visit = tree[node].visit;
+            let (action, child) = tree
+                .children(node)
+                .map(|(action, child)| {
+                    let eval = if tree[child].visit == 0 {
+                        config.fpu
+                            + config.puct.c * tree[child].prior * (parent_visit as f64).sqrt()
+                    } else {
+                        config.puct.eval(parent_visit, &tree[child])
+                    };
+                    (action, child, eval)
+                })
+                .max_by(|a, b| a.2.total_cmp(&b.2))
+                .map(|(action, child, _)| (action, child))
+                .unwrap();
+
+            // Keep the player who moved
+            path.push((child, self.turn() % Self::PLAYERS));
+            self.update(action);
+            node = child;
+        };
+
+        for &(node, player) in path.iter() {
+            tree[node].backpropagate(leaf.score(player));
+        }
+        let depth = path.len();
+        tree.moves = path;
+
+        (leaf, depth)
+    }
+
+    /// The root holds the rewards of the player to move
+    ///
+    /// The noise of the config is mixed into the root priors after the first iteration.
+    fn az_mcts<const IN: usize, const A: usize>(
+        &self,
+        rng: &mut ThreadRng,
+        config: &AlphaZeroConfig,
+        net: &mut PolicyValue<IN, A>,
+        tree: &mut Tree<usize>,
+        actions: &mut Vec<usize>,
+    ) -> BudgetStats {
+        let mut watch = config.budget.start();
+        let player = self.turn() % Self::PLAYERS;
+
+        while watch.next_iteration() {
+            if tree.is_full() {
+                tree.prune(tree.max_nodes() / 2);
+            }
+            let (leaf, depth) = self.clone().az_rollout(config, net, tree, actions);
+            tree[0].backpropagate(leaf.score(player));
+            watch.add_nodes(depth);
+
+            if let (1, Some((alpha, weight))) = (watch.iterations(), config.noise) {
+                let gamma = Gamma::new(alpha, 1.).unwrap();
+                let children = tree.children(0).map(|(_, c)| c).collect::<Vec<_>>();
+                let noise = children
+                    .iter()
+                    .map(|_| gamma.sample(rng))
+                    .collect::<Vec<f64>>();
+                let total = noise.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+                for (child, noise) in children.into_iter().zip(noise) {
+                    let prior = &mut tree[child].prior;
+                    *prior = (1. - weight) * *prior + weight * noise / total;
+                }
+            }
+        }
+        watch.stats()
+    }
+
+    /// Play a game against itself, `samples` receives its positions
+    /// Return the final status
+    fn az_self_play<const IN: usize, const A: usize>(
+        &self,
+        rng: &mut ThreadRng,
+        config: &AlphaZeroConfig,
+        net: &mut PolicyValue<IN, A>,
+        samples: &mut Vec<Sample<IN, A>>,
+        actions: &mut Vec<usize>,
+    ) -> Status {
+        let mut game = self.clone();
+        let mut players = Vec::new();
+        let start = samples.len();
+        let mut tree = Tree::with_max_nodes(config.max_nodes);
+        game.fill(actions);
+
+        while !actions.is_empty() {
+            game.az_mcts(rng, config, net, &mut tree, actions);
+
+            let mut policy = [0.; A];
+            let total = tree.children(0).map(|(_, c)| tree[c].visit()).sum::<u64>();
+            let total = total.max(1) as f32;
+            tree.children(0)
+                .for_each(|(a, child)| policy[a] = tree[child].visit() as f32 / total);
+
+            let action = if players.len() < config.temperature_moves {
+                let children = tree.children(0).collect::<Vec<_>>();
+                children
+                    .choose_weighted(rng, |(_, child)| tree[*child].visit())
+                    .map_or_else(|_| tree.best_action().unwrap(), |(a, _)| *a)
+            } else {
+                tree.best_action().unwrap()
+            };
+
+            samples.push(Sample {
+                input: game.input(),
+                policy,
+                value: 0.,
+            });
+            players.push(game.turn() % Self::PLAYERS);
+            tree.advance(action);
+            game.update(action);
+            game.fill(actions);
+        }
+
+        let status = game.status();
+        for (sample, &player) in samples[start..].iter_mut().zip(&players) {
+            sample.value = 2. * outcome(status, player) as f32 - 1.;
+        }
+        status
+    }
+
+    /// Self-play games, the network learns from the last `capacity` samples after each one
+    ///
+    /// One iteration is one game and one node is one move. Return the mean loss.
+    fn az_train<const IN: usize, const A: usize>(
+        &self,
+        rng: &mut ThreadRng,
+        budget: &Budget,
+        config: &AlphaZeroConfig,
+        net: &mut PolicyValue<IN, A>,
+        samples: &mut Vec<Sample<IN, A>>,
+        actions: &mut Vec<usize>,
+    ) -> (f32, BudgetStats) {
+        let mut watch = budget.start();
+        let mut total = 0.;
+
+        while watch.next_iteration() {
+            let len = samples.len();
+            self.az_self_play(rng, config, net, samples, actions);
+            watch.add_nodes(samples.len() - len);
+
+            if samples.len() > config.capacity {
+                samples.drain(..samples.len() - config.capacity);
+            }
+            total += net.learn(rng, samples, config.batch_size);
+        }
+
+        (total / watch.iterations().max(1) as f32, watch.stats())
+    }
+}
+
+impl<G: Game> AlphaZero for G {}
+
+/// `AlphaZero::az_mcts` from scratch at each move, without noise
+#[derive(Debug, Clone)]
+pub struct AlphaZeroAgent<const IN: usize, const A: usize> {
+    pub net: PolicyValue<IN, A>,
+    pub config: AlphaZeroConfig,
+    actions: Vec<usize>,
+}
+
+impl<const IN: usize, const A: usize> AlphaZeroAgent<IN, A> {
+    pub fn new(net: PolicyValue<IN, A>, mut config: AlphaZeroConfig) -> Self {
+        config.noise = None;
+        Self {
+            net,
+            config,
+            actions: Vec::new(),
+        }
+    }
+}
+
+impl<G: Game, const IN: usize, const A: usize> Agent<G> for AlphaZeroAgent<IN, A> {
+    fn act(&mut self, rng: &mut ThreadRng, game: &G, actions: &[usize]) -> usize {
+        let mut tree = Tree::with_max_nodes(self.config.max_nodes);
+        game.az_mcts(
+            rng,
+            &self.config,
+            &mut self.net,
+            &mut tree,
+            &mut self.actions,
+        );
+        tree.best_action().unwrap_or(actions[0])
+    }
+}
+
+#[test]
+fn alpha_zero() {
+    use crate::{arena, Optimizer, RandomAgent};
+
+    /// Take 1 to 3 stones, the player taking the last one wins
+    #[derive(Debug, Clone)]
+    struct Nim {
+        stones: usize,
+        turn: usize,
+    }
+
+    impl Game for Nim {
+        fn input<const N: usize>(&self) -> [f32; N] {
+            let mut input = [0.; N];
+            input[self.stones] = 1.;
+            input
+        }
+        fn turn(&self) -> usize {
+            self.turn
+        }
+        fn status(&self) -> Status {
+            if self.stones == 0 {
+                Status::Win((self.turn + 1) % 2)
+            } else {
+                Status::None
+            }
+        }
+        fn reward(&self, player: usize) -> f32 {
+            outcome(self.status(), player) as f32
+        }
+        fn fill(&self, actions: &mut Vec<usize>) {
+            actions.clear();
+            actions.extend((1..=3).filter(|&n| n <= self.stones));
+        }
+        fn update(&mut self, action: usize) {
+            self.stones -= action;
+            self.turn += 1;
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut actions = Vec::new();
+    let mut net = PolicyValue::<10, 4>::new(&[32])
+        .with_optimizer(OptimizerConfig::new(Optimizer::adam(), 0.01));
+    let config = AlphaZeroConfig {
+        budget: Budget::iterations(50),
+        batch_size: 16,
+        ..Default::default()
+    };
+
+    let game = Nim { stones: 9, turn: 0 };
+    let mut samples = Vec::new();
+    let (_, stats) = game.az_train(
+        &mut rng,
+        &Budget::iterations(100),
+        &config,
+        &mut net,
+        &mut samples,
+        &mut actions,
+    );
+    assert_eq!(stats.iterations, 100);
+    assert_eq!(samples.len(), stats.nodes);
+    assert!(samples
+        .iter()
+        .all(|s| (s.policy.iter().sum::<f32>() - 1.).abs() < 1e-5 && s.value.abs() == 1.));
+
+    let (policy, value) = net.predict(game.input());
+    assert!((policy.iter().sum::<f32>() - 1.).abs() < 1e-5 && value.abs() <= 1.);
+
+    // Random play scores 0.5, the first player wins from 9 stones
+    let mut agent = AlphaZeroAgent::new(net, config);
+    let (stats, _) = arena(
+        &mut rng,
+        &Budget::iterations(50),
+        &game,
+        &mut agent,
+        &mut RandomAgent,
+        &mut actions,
+    );
+    assert!(stats.score() > 0.7, "{stats:?}");
+}
@@ -0,0 +1,377 @@
+use crate::{
+    Budget, BudgetStats, DefaultPolicy, Dqn, Game, GameMcts, MctsConfig, Negamax, Selection,
+    Status, TranspositionTable, Tree, NN,
+};
+use rand::prelude::{SliceRandom, ThreadRng};
+
+/// Player of a `Game`
+pub trait Agent<G: Game> {
+    /// Pick one of the legal `actions`, which are not empty
+    fn act(&mut self, rng: &mut ThreadRng, game: &G, actions: &[usize]) -> usize;
+}
+
+/// Uniformly random moves
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomAgent;
+
+impl<G: Game> Agent<G> for RandomAgent {
+    fn act(&mut self, rng: &mut ThreadRng, _: &G, actions: &[usize]) -> usize {
+        *actions.choose(rng).unwrap()
+    }
+}
+
+/// Moves given by a function of the game and the legal actions
+#[derive(Debug, Clone, Copy)]
+pub struct Scripted<F>(pub F);
+
+impl<G: Game, F: FnMut(&G, &[usize]) -> usize> Agent<G> for Scripted<F> {
+    fn act(&mut self, _: &mut ThreadRng, game: &G, actions: &[usize]) -> usize {
+        (self.0)(game, actions)
+    }
+}
+
+/// Legal action with the highest output
+impl<G: Game, const IN: usize, const OUT: usize> Agent<G> for NN<IN, OUT> {
+    fn act(&mut self, _: &mut ThreadRng, game: &G, actions: &[usize]) -> usize {
+        self.best_action(game.input(), actions).unwrap()
+    }
+}
+
+/// Greedy with the online network
+impl<G: Game, const IN: usize, const OUT: usize> Agent<G> for Dqn<IN, OUT> {
+    fn act(&mut self, rng: &mut ThreadRng, game: &G, actions: &[usize]) -> usize {
+        self.online.act(rng, game, actions)
+    }
+}
+
+/// `GameMcts` from scratch at each move, with a tree of at most `max_nodes`
+#[derive(Debug, Clone)]
+pub struct MctsAgent<S, P> {
+    pub budget: Budget,
+    pub config: MctsConfig<S, P>,
+    pub max_nodes: usize,
+    actions: Vec<usize>,
+}
+
+impl<S, P> MctsAgent<S, P> {
+    pub fn new(budget: Budget, config: MctsConfig<S, P>, max_nodes: usize) -> Self {
+        Self {
+            budget,
+            config,
+            max_nodes,
+            actions: Vec::new(),
+        }
+    }
+}
+
+impl<G: Game, S: Selection, P: DefaultPolicy<G, usize>> Agent<G> for MctsAgent<S, P> {
+    fn act(&mut self, rng: &mut ThreadRng, game: &G, actions: &[usize]) -> usize {
+        let mut tree = Tree::with_max_nodes(self.max_nodes);
+        game.game_mcts(
+            rng,
+            &self.budget,
+            &self.config,
+            &mut tree,
+            &mut self.actions,
+        );
+        tree.best_action().unwrap_or(actions[0])
+    }
+}
+
+/// `Negamax::iterative_deepening`, the table is kept between moves
+#[derive(Debug, Clone)]
+pub struct NegamaxAgent {
+    pub budget: Budget,
+    pub tt: TranspositionTable,
+}
+
+impl NegamaxAgent {
+    pub fn new(budget: Budget, bits: u32) -> Self {
+        Self {
+            budget,
+            tt: TranspositionTable::new(bits),
+        }
+    }
+}
+
+impl<G: Negamax> Agent<G> for NegamaxAgent {
+    fn act(&mut self, _: &mut ThreadRng, game: &G, actions: &[usize]) -> usize {
+        game.iterative_deepening(&self.budget, &mut self.tt)
+            .map_or(actions[0], |(action, _, _)| action)
+    }
+}
+
+/// Elo difference matching an expected score
+pub fn elo(score: f64) -> f64 {
+    -400. * (1. / score - 1.).log10()
+}
+
+/// Results of the first agent, a game ended without status is a draw
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArenaStats {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl ArenaStats {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 1 per win and 0.5 per draw, averaged
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.) / self.games().max(1) as f64
+    }
+
+    /// Wilson interval of the score, `z` standard deviations wide on each side
+    pub fn confidence(&self, z: f64) -> (f64, f64) {
+        let n = self.games() as f64;
+        if n == 0. {
+            return (0., 1.);
+        }
+        let p = self.score();
+        let z2 = z * z;
+        let centre = (p + z2 / (2. * n)) / (1. + z2 / n);
+        let half = z / (1. + z2 / n) * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt();
+        (centre - half, centre + half)
+    }
+
+    /// Elo difference with the second agent, infinite without loss or without win
+    pub fn elo(&self) -> f64 {
+        elo(self.score())
+    }
+
+    /// Elo bounds from the confidence interval of the score
+    pub fn elo_confidence(&self, z: f64) -> (f64, f64) {
+        let (low, high) = self.confidence(z);
+        (elo(low), elo(high))
+    }
+}
+
+/// Play games between two agents, the first one plays first in the even games
+///
+/// One iteration is one game and one node is one move.
+pub fn arena<G: Game>(
+    rng: &mut ThreadRng,
+    budget: &Budget,
+    game: &G,
+    agent0: &mut impl Agent<G>,
+    agent1: &mut impl Agent<G>,
+    actions: &mut Vec<usize>,
+) -> (ArenaStats, BudgetStats) {
+    assert_eq!(G::PLAYERS, 2);
+    let mut watch = budget.start();
+    let mut stats = ArenaStats::default();
+
+    while watch.next_iteration() {
+        // Player of the first agent
+        let side = (watch.iterations() - 1) % 2;
+        let mut game = game.clone();
+        game.fill(actions);
+
+        while !actions.is_empty() {
+            let action = if game.turn() % 2 == side {
+                agent0.act(rng, &game, actions)
+            } else {
+                agent1.act(rng, &game, actions)
+            };
+            game.update(action);
+            watch.add_nodes(1);
+            game.fill(actions);
+        }
+
+        match game.status() {
+            Status::Win(winner) if winner == side => stats.wins += 1,
+            Status::Win(_) => stats.losses += 1,
+            Status::Draw | Status::None => stats.draws += 1,
+        }
+    }
+
+    (stats, watch.stats())
+}
+
+/// - games: Arena games between the candidate and the best checkpoint
+/// - threshold: Score needed by the candidate to become the best checkpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfPlayConfig {
+    pub games: usize,
+    pub threshold: f64,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        Self {
+            games: 100,
+            threshold: 0.55,
+        }
+    }
+}
+
+/// Alternate training and evaluation against the best checkpoint, which is returned
+///
+/// One iteration is one generation: `train` is called once, then the candidate meets the
+/// checkpoint in the arena. `history` receives the results of each generation.
+#[allow(clippy::too_many_arguments)]
+pub fn self_play<G: Game, T: Agent<G> + Clone>(
+    rng: &mut ThreadRng,
+    budget: &Budget,
+    config: &SelfPlayConfig,
+    game: &G,
+    candidate: &mut T,
+    mut train: impl FnMut(&mut ThreadRng, &mut T),
+    actions: &mut Vec<usize>,
+    history: &mut Vec<ArenaStats>,
+) -> (T, BudgetStats) {
+    let mut watch = budget.start();
+    let mut best = candidate.clone();
+    let games = Budget::iterations(config.games);
+    history.clear();
+
+    while watch.next_iteration() {
+        train(rng, candidate);
+        let (stats, arena_stats) = arena(rng, &games, game, candidate, &mut best, actions);
+        watch.add_nodes(arena_stats.nodes);
+        history.push(stats);
+
+        if stats.score() >= config.threshold {
+            best.clone_from(candidate);
+        }
+    }
+
+    (best, watch.stats())
+}
+
+#[test]
+fn arena_nim() {
+    use crate::{outcome, Activation, DqnConfig, Epsilon};
+
+    /// Take 1 to 3 stones, the player taking the last one wins
+    #[derive(Debug, Clone)]
+    struct Nim {
+        stones: usize,
+        turn: usize,
+    }
+
+    impl Game for Nim {
+        fn input<const N: usize>(&self) -> [f32; N] {
+            let mut input = [0.; N];
+            input[self.stones] = 1.;
+            input
+        }
+        fn turn(&self) -> usize {
+            self.turn
+        }
+        fn status(&self) -> Status {
+            if self.stones == 0 {
+                Status::Win((self.turn + 1) % 2)
+            } else {
+                Status::None
+            }
+        }
+        fn reward(&self, player: usize) -> f32 {
+            outcome(self.status(), player) as f32
+        }
+        fn fill(&self, actions: &mut Vec<usize>) {
+            actions.clear();
+            actions.extend((1..=3).filter(|&n| n <= self.stones));
+        }
+        fn update(&mut self, action: usize) {
+            self.stones -= action;
+            self.turn += 1;
+        }
+    }
+
+    impl Negamax for Nim {
+        fn key(&self) -> u64 {
+            (self.stones * 2 + self.turn % 2) as u64
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut actions = Vec::new();
+    let game = Nim { stones: 9, turn: 0 };
+    let budget = Budget::iterations(100);
+
+    // Leave a multiple of 4 stones when possible
+    let mut perfect = Scripted(|game: &Nim, actions: &[usize]| {
+        actions
+            .iter()
+            .copied()
+            .find(|&a| (game.stones - a).is_multiple_of(4))
+            .unwrap_or(actions[0])
+    });
+    let mut negamax = NegamaxAgent::new(Budget::iterations(20), 10);
+    let (stats, arena_stats) = arena(
+        &mut rng,
+        &budget,
+        &game,
+        &mut perfect,
+        &mut negamax,
+        &mut actions,
+    );
+    // The first player wins from 9 stones
+    assert_eq!(
+        stats,
+        ArenaStats {
+            wins: 50,
+            draws: 0,
+            losses: 50
+        }
+    );
+    assert_eq!(arena_stats.iterations, 100);
+    assert_eq!(stats.elo(), 0.);
+
+    let config: MctsConfig = MctsConfig::default();
+    let mut mcts = MctsAgent::new(Budget::iterations(300), config, 1000);
+    let (stats, _) = arena(
+        &mut rng,
+        &budget,
+        &game,
+        &mut mcts,
+        &mut RandomAgent,
+        &mut actions,
+    );
+    assert!(stats.score() > 0.7, "{stats:?}");
+
+    let stats = ArenaStats {
+        wins: 60,
+        draws: 0,
+        losses: 40,
+    };
+    assert!((stats.elo() - 70.4).abs() < 0.1);
+    let (low, high) = stats.confidence(1.96);
+    assert!((low - 0.502).abs() < 1e-3 && (high - 0.691).abs() < 1e-3);
+    let (low, high) = stats.elo_confidence(1.96);
+    assert!(0. < low && low < 70. && 71. < high && high < 140.);
+
+    // Each generation meets the checkpoint
+    let nn = NN::<10, 4>::builder()
+        .dense(16, Activation::Relu)
+        .output(Activation::Linear);
+    let config = DqnConfig::default()
+        .with_epsilon(Epsilon::Constant(0.3))
+        .with_warmup(32);
+    let mut dqn = Dqn::new(nn, config);
+    let mut history = Vec::new();
+    let mut train_actions = Vec::new();
+    let config = SelfPlayConfig {
+        games: 20,
+        threshold: 0.55,
+    };
+    let (_, stats) = self_play(
+        &mut rng,
+        &Budget::iterations(3),
+        &config,
+        &game,
+        &mut dqn,
+        |rng, dqn| {
+            dqn.train(rng, &Budget::iterations(50), &game, &mut train_actions);
+        },
+        &mut actions,
+        &mut history,
+    );
+    assert_eq!(stats.iterations, 3);
+    assert_eq!(history.len(), 3);
+    assert!(history.iter().all(|stats| stats.games() == 20));
+}
@@ -0,0 +1,155 @@
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use simd::{F32x4, F32x8};
+
+/// Products of the dense layers, every backend gives the same bits
+/// - Scalar: Portable, with 8 accumulators like the vector backends
+/// - Sse: `simd::F32x4`
+/// - Avx: `simd::F32x8`
+///
+/// A backend which the CPU doesn't support falls back to `Scalar`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Scalar,
+    Sse,
+    Avx,
+}
+
+impl Backend {
+    /// Fastest backend supported by the CPU
+    pub fn detect() -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx") {
+                return Backend::Avx;
+            }
+            if is_x86_feature_detected!("sse") {
+                return Backend::Sse;
+            }
+        }
+        Backend::Scalar
+    }
+
+    /// Sum of the products, by 8 lanes then the remainder in order
+    pub fn dot(&self, a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let split = n - n % 8;
+        let (a8, b8) = (&a[..split], &b[..split]);
+
+        let lanes = match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx if is_x86_feature_detected!("avx") => unsafe { dot_avx(a8, b8) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse if is_x86_feature_detected!("sse") => unsafe { dot_sse(a8, b8) },
+            _ => dot_scalar(a8, b8),
+        };
+
+        let mut out = (lanes[0] + lanes[1]) + (lanes[2] + lanes[3]);
+        for (x, y) in a[split..n].iter().zip(&b[split..n]) {
+            out += x * y;
+        }
+        out
+    }
+
+    /// y += a * x
+    pub fn axpy(&self, y: &mut [f32], a: f32, x: &[f32]) {
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx if is_x86_feature_detected!("avx") => unsafe { axpy_avx(y, a, x) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse if is_x86_feature_detected!("sse") => unsafe { axpy_sse(y, a, x) },
+            _ => y.iter_mut().zip(x).for_each(|(y, x)| *y += a * x),
+        }
+    }
+}
+
+/// Lanes i and i + 4 of the 8 accumulators are added
+fn dot_scalar(a: &[f32], b: &[f32]) -> [f32; 4] {
+    let mut acc = [0f32; 8];
+    for (a, b) in a.chunks_exact(8).zip(b.chunks_exact(8)) {
+        for k in 0..8 {
+            acc[k] += a[k] * b[k];
+        }
+    }
+    [0, 1, 2, 3].map(|k| acc[k] + acc[k + 4])
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse")]
+unsafe fn dot_sse(a: &[f32], b: &[f32]) -> [f32; 4] {
+    let (mut low, mut high) = (F32x4::zero(), F32x4::zero());
+    for (a, b) in a.chunks_exact(8).zip(b.chunks_exact(8)) {
+        low += F32x4::new(a[0], a[1], a[2], a[3]) * F32x4::new(b[0], b[1], b[2], b[3]);
+        high += F32x4::new(a[4], a[5], a[6], a[7]) * F32x4::new(b[4], b[5], b[6], b[7]);
+    }
+    (low + high).to_array()
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx")]
+unsafe fn dot_avx(a: &[f32], b: &[f32]) -> [f32; 4] {
+    let mut acc = F32x8::zero();
+    for (a, b) in a.chunks_exact(8).zip(b.chunks_exact(8)) {
+        acc += F32x8::from_array(a.try_into().unwrap()) * F32x8::from_array(b.try_into().unwrap());
+    }
+    let acc = acc.to_array();
+    [0, 1, 2, 3].map(|k| acc[k] + acc[k + 4])
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse")]
+unsafe fn axpy_sse(y: &mut [f32], a: f32, x: &[f32]) {
+    let n = y.len().min(x.len());
+    let split = n - n % 4;
+    let a4 = F32x4::broadcast_with(a);
+    for (y, x) in y[..split].chunks_exact_mut(4).zip(x.chunks_exact(4)) {
+        let sum = F32x4::new(y[0], y[1], y[2], y[3]) + a4 * F32x4::new(x[0], x[1], x[2], x[3]);
+        y.copy_from_slice(&sum.to_array());
+    }
+    y[split..n]
+        .iter_mut()
+        .zip(&x[split..n])
+        .for_each(|(y, x)| *y += a * x);
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx")]
+unsafe fn axpy_avx(y: &mut [f32], a: f32, x: &[f32]) {
+    let n = y.len().min(x.len());
+    let split = n - n % 8;
+    let a8 = F32x8::broadcast_with(a);
+    for (y, x) in y[..split].chunks_exact_mut(8).zip(x.chunks_exact(8)) {
+        let x = F32x8::from_array(x.try_into().unwrap());
+        let sum = F32x8::from_array((&*y).try_into().unwrap()) + a8 * x;
+        y.copy_from_slice(&sum.to_array());
+    }
+    y[split..n]
+        .iter_mut()
+        .zip(&x[split..n])
+        .for_each(|(y, x)| *y += a * x);
+}
+
+#[test]
+fn backend() {
+    let a = (0..37).map(|i| (i as f32 * 0.37).sin()).collect::<Vec<_>>();
+    let b = (0..37).map(|i| (i as f32 * 1.3).cos()).collect::<Vec<_>>();
+    let expected = a.iter().zip(&b).map(|(x, y)| (x * y) as f64).sum::<f64>();
+
+    let scalar = Backend::Scalar.dot(&a, &b);
+    assert!((scalar as f64 - expected).abs() < 1e-5);
+    let mut y = b.clone();
+    Backend::Scalar.axpy(&mut y, 0.3, &a);
+
+    for backend in [Backend::Sse, Backend::Avx, Backend::detect()] {
+        for n in [0, 3, 8, 16, 37] {
+            assert_eq!(
+                backend.dot(&a[..n], &b[..n]).to_bits(),
+                Backend::Scalar.dot(&a[..n], &b[..n]).to_bits(),
+                "{backend:?} {n}"
+            );
+        }
+        let mut z = b.clone();
+        backend.axpy(&mut z, 0.3, &a);
+        assert_eq!(y, z, "{backend:?}");
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Stopping policy shared by the searches
+/// - time: Wall-clock limit
+/// - iterations: Number of iterations (rollouts, generations, ...)
+/// - nodes: Number of states generated (calls to `update`, chromosome evaluations)
+/// - cancel: External flag, the search stops as soon as it is set
+///
+/// Limits are combined, the first one reached stops the search.
+/// Without any limit nor cancel flag, no iteration is run.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    pub time: Option<Duration>,
+    pub iterations: Option<usize>,
+    pub nodes: Option<usize>,
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl From<Duration> for Budget {
+    fn from(time: Duration) -> Self {
+        Self::time(time)
+    }
+}
+
+impl Budget {
+    pub fn time(time: Duration) -> Self {
+        Self::default().with_time(time)
+    }
+
+    pub fn iterations(iterations: usize) -> Self {
+        Self::default().with_iterations(iterations)
+    }
+
+    pub fn nodes(nodes: usize) -> Self {
+        Self::default().with_nodes(nodes)
+    }
+
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = Some(iterations);
+        self
+    }
+
+    pub fn with_nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Only count based limits, the result does not depend on the machine
+    pub fn is_deterministic(&self) -> bool {
+        self.time.is_none() && (self.iterations.is_some() || self.nodes.is_some())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Share the budget evenly between `n` sub-searches, the cancel flag is shared
+    pub fn split(&self, n: usize) -> Self {
+        let n = n.max(1);
+        Self {
+            time: self
+                .time
+                .map(|time| Duration::from_nanos((time.as_nanos() / n as u128) as u64)),
+            iterations: self.iterations.map(|i| i / n),
+            nodes: self.nodes.map(|i| i / n),
+            cancel: self.cancel.clone(),
+        }
+    }
+
+    /// Part of the `thread`-th among `n` threads running at the same time,
+    /// the time limit is kept and the counts are split
+    pub fn share(&self, n: usize, thread: usize) -> Self {
+        let n = n.max(1);
+        let part = |count: usize| count / n + usize::from(thread < count % n);
+        Self {
+            time: self.time,
+            iterations: self.iterations.map(part),
+            nodes: self.nodes.map(part),
+            cancel: self.cancel.clone(),
+        }
+    }
+
+    pub fn start(&self) -> Stopwatch<'_> {
+        let now = Instant::now();
+        Stopwatch {
+            budget: self,
+            start: now,
+            lap: now,
+            max_lap: Duration::default(),
+            stats: BudgetStats::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Time,
+    Iterations,
+    Nodes,
+    Cancelled,
+}
+
+/// How much of a budget a search used
+/// - stop: None if the search ended on its own (nothing left to explore)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BudgetStats {
+    pub elapsed: Duration,
+    pub iterations: usize,
+    pub nodes: usize,
+    pub stop: Option<StopReason>,
+}
+
+impl BudgetStats {
+    /// Fraction of the most consumed limit
+    pub fn usage(&self, budget: &Budget) -> f64 {
+        let time = budget
+            .time
+            .map(|t| self.elapsed.as_secs_f64() / t.as_secs_f64().max(f64::EPSILON));
+        let iterations = budget
+            .iterations
+            .map(|i| self.iterations as f64 / i.max(1) as f64);
+        let nodes = budget.nodes.map(|n| self.nodes as f64 / n.max(1) as f64);
+
+        [time, iterations, nodes]
+            .into_iter()
+            .flatten()
+            .fold(0., f64::max)
+    }
+}
+
+impl std::ops::AddAssign for BudgetStats {
+    /// Merge the stats of sub-searches run one after the other
+    fn add_assign(&mut self, rhs: Self) {
+        self.elapsed += rhs.elapsed;
+        self.iterations += rhs.iterations;
+        self.nodes += rhs.nodes;
+        self.stop = rhs.stop.or(self.stop);
+    }
+}
+
+/// Running budget of a search
+///
+/// The time limit keeps the margin of the longest iteration seen so far,
+/// so that the search never exceeds it.
+pub struct Stopwatch<'a> {
+    budget: &'a Budget,
+    start: Instant,
+    lap: Instant,
+    max_lap: Duration,
+    stats: BudgetStats,
+}
+
+impl Stopwatch<'_> {
+    /// Close the previous iteration and check if another one can start
+    pub fn next_iteration(&mut self) -> bool {
+        let now = Instant::now();
+        if self.stats.iterations > 0 {
+            self.max_lap = std::cmp::max(self.max_lap, now - self.lap);
+        }
+        self.lap = now;
+        self.stats.elapsed = now - self.start;
+        self.stats.stop = self.check();
+
+        let unlimited = self.budget.time.is_none()
+            && self.budget.iterations.is_none()
+            && self.budget.nodes.is_none()
+            && self.budget.cancel.is_none();
+
+        if self.stats.stop.is_some() || unlimited {
+            return false;
+        }
+        self.stats.iterations += 1;
+        true
+    }
+
+    fn check(&self) -> Option<StopReason> {
+        if self.budget.is_cancelled() {
+            Some(StopReason::Cancelled)
+        } else if matches!(self.budget.iterations, Some(i) if self.stats.iterations >= i) {
+            Some(StopReason::Iterations)
+        } else if matches!(self.budget.nodes, Some(n) if self.stats.nodes >= n) {
+            Some(StopReason::Nodes)
+        } else if matches!(self.budget.time, Some(t) if self.stats.elapsed + self.max_lap >= t) {
+            Some(StopReason::Time)
+        } else {
+            None
+        }
+    }
+
+    pub fn add_nodes(&mut self, nodes: usize) {
+        self.stats.nodes += nodes;
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.stats.iterations
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.stats.nodes
+    }
+
+    /// Check the time, nodes and cancel limits during an iteration, without margin
+    pub fn is_over(&self) -> bool {
+        self.budget.is_cancelled()
+            || matches!(self.budget.nodes, Some(n) if self.stats.nodes >= n)
+            || matches!(self.budget.time, Some(t) if self.start.elapsed() >= t)
+    }
+
+    pub fn stats(&self) -> BudgetStats {
+        BudgetStats {
+            elapsed: self.start.elapsed(),
+            ..self.stats
+        }
+    }
+
+    /// Fraction of the budget used so far, see `BudgetStats::usage`
+    pub fn usage(&self) -> f64 {
+        self.stats().usage(self.budget)
+    }
+}
+
+#[test]
+fn budget() {
+    let budget = Budget::iterations(10).with_nodes(25);
+    let mut watch = budget.start();
+    while watch.next_iteration() {
+        watch.add_nodes(3);
+    }
+    let stats = watch.stats();
+    assert_eq!(stats.iterations, 9);
+    assert_eq!(stats.stop, Some(StopReason::Nodes));
+    assert!(budget.is_deterministic());
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let budget = Budget::time(Duration::from_secs(60)).with_cancel(cancel.clone());
+    let mut watch = budget.start();
+    while watch.next_iteration() {
+        if watch.iterations() == 5 {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+    assert_eq!(watch.stats().iterations, 5);
+    assert_eq!(watch.stats().stop, Some(StopReason::Cancelled));
+
+    let budget = Budget::default();
+    let mut watch = budget.start();
+    assert!(!watch.next_iteration());
+    assert_eq!(Budget::iterations(10).split(3).iterations, Some(3));
+    assert_eq!(Budget::iterations(0).split(3).iterations, Some(0));
+    assert_eq!(Budget::nodes(2).split(3).nodes, Some(0));
+}
@@ -0,0 +1,544 @@
+use crate::{Budget, BudgetStats};
+use rand::prelude::ThreadRng;
+use rand_distr::{Distribution, StandardNormal};
+use std::collections::VecDeque;
+use vector::VectorOp;
+
+/// a += x * b
+fn add_scaled(a: &mut [f64], x: f64, b: &[f64]) {
+    a.iter_mut().zip(b).for_each(|(ai, bi)| *ai += x * *bi);
+}
+
+fn norm(a: &[f64]) -> f64 {
+    VectorOp::dot(a, a).sqrt()
+}
+
+/// Eigen decomposition of a symmetric matrix of size n, stored by rows
+/// Return the eigenvalues and the eigenvectors as columns
+fn jacobi(mut a: Vec<f64>, n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut v = vec![0.; n * n];
+    (0..n).for_each(|i| v[i * n + i] = 1.);
+
+    for _ in 0..50 {
+        let off = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i * n + j] * a[i * n + j])
+            .sum::<f64>();
+        if off < 1e-22 {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p * n + q].abs() < 1e-300 {
+                    continue;
+                }
+                // Rotation cancelling a[p][q]
+                let theta = (a[q * n + q] - a[p * n + p]) / (2. * a[p * n + q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                let c = 1. / (t * t + 1.).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[k * n + p], v[k * n + q]);
+                    v[k * n + p] = c * vkp - s * vkq;
+                    v[k * n + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    ((0..n).map(|i| a[i * n + i]).collect(), v)
+}
+
+/// Function of a `f64` vector to minimise, `[f64; N]` and `Vec<f64>` are used as slices
+///
+/// The solvers start from `x` and leave the best point found in it, they return its value.
+/// One node is one call to `value` or `gradient`, the default `gradient` counting as one
+/// although it calls `value` twice per coordinate. `history` receives the best value
+/// before the first iteration and after each one. The search ends on its own
+/// (`BudgetStats::stop` is None) when the convergence criterion is met.
+pub trait Objective {
+    fn value(&self, x: &[f64]) -> f64;
+
+    /// Central finite differences, override with the exact gradient if known
+    fn gradient(&self, x: &[f64], gradient: &mut [f64]) {
+        let mut x = x.to_vec();
+        for i in 0..x.len() {
+            let xi = x[i];
+            let h = 1e-6 * xi.abs().max(1.);
+            x[i] = xi + h;
+            let up = self.value(&x);
+            x[i] = xi - h;
+            let down = self.value(&x);
+            x[i] = xi;
+            gradient[i] = (up - down) / (2. * h);
+        }
+    }
+
+    /// Backtracking line search with the Armijo condition along a descent direction
+    /// Return the step and the new value if found, `next` receives the new point, and the
+    /// number of calls to `value`
+    fn line_search(
+        &self,
+        x: &[f64],
+        value: f64,
+        gradient: &[f64],
+        direction: &[f64],
+        next: &mut [f64],
+    ) -> (Option<(f64, f64)>, usize) {
+        let slope = VectorOp::dot(gradient, direction);
+        if slope >= 0. {
+            return (None, 0);
+        }
+        let mut step = 1.;
+        for calls in 1..=60 {
+            next.copy_from_slice(x);
+            add_scaled(next, step, direction);
+            let next_value = self.value(next);
+            if next_value <= value + 1e-4 * step * slope {
+                return (Some((step, next_value)), calls);
+            }
+            step *= 0.5;
+        }
+        (None, 60)
+    }
+
+    /// Derivative-free simplex method
+    /// - step: Size of the initial simplex around `x`
+    /// - tolerance: Stop when the values of the simplex differ by less
+    fn nelder_mead(
+        &self,
+        x: &mut [f64],
+        step: f64,
+        tolerance: f64,
+        budget: &Budget,
+        history: &mut Vec<f64>,
+    ) -> (f64, BudgetStats) {
+        let n = x.len();
+        let mut watch = budget.start();
+        history.clear();
+
+        let mut simplex = (0..=n)
+            .map(|i| {
+                let mut point = x.to_vec();
+                if i > 0 {
+                    point[i - 1] += step;
+                }
+                let value = self.value(&point);
+                (value, point)
+            })
+            .collect::<Vec<_>>();
+        watch.add_nodes(n + 1);
+        simplex.sort_by(|a, b| a.0.total_cmp(&b.0));
+        history.push(simplex[0].0);
+
+        let mut centroid = vec![0.; n];
+        let mut trial = vec![0.; n];
+        let mut trial2 = vec![0.; n];
+        while simplex[n].0 - simplex[0].0 >= tolerance && watch.next_iteration() {
+            // Centroid of all points but the worst
+            centroid.iter_mut().for_each(|c| *c = 0.);
+            simplex[..n]
+                .iter()
+                .for_each(|(_, point)| VectorOp::add_assign(&mut centroid, point));
+            VectorOp::mul_assign_with(&mut centroid, 1. / n as f64);
+
+            // Point on the line from the worst through the centroid
+            let along = |t: f64, out: &mut [f64], worst: &[f64]| {
+                out.copy_from_slice(&centroid);
+                out.iter_mut()
+                    .zip(worst)
+                    .for_each(|(o, w)| *o += t * (*o - w));
+            };
+
+            along(1., &mut trial, &simplex[n].1);
+            let reflected = self.value(&trial);
+            watch.add_nodes(1);
+
+            if reflected < simplex[0].0 {
+                along(2., &mut trial2, &simplex[n].1);
+                let expanded = self.value(&trial2);
+                watch.add_nodes(1);
+                if expanded < reflected {
+                    simplex[n] = (expanded, trial2.clone());
+                } else {
+                    simplex[n] = (reflected, trial.clone());
+                }
+            } else if reflected < simplex[n - 1].0 {
+                simplex[n] = (reflected, trial.clone());
+            } else {
+                // Contraction, outside if the reflection is better than the worst
+                let t = if reflected < simplex[n].0 { 0.5 } else { -0.5 };
+                along(t, &mut trial2, &simplex[n].1);
+                let contracted = self.value(&trial2);
+                watch.add_nodes(1);
+                if contracted < reflected.min(simplex[n].0) {
+                    simplex[n] = (contracted, trial2.clone());
+                } else {
+                    // Shrink toward the best point
+                    let best = simplex[0].1.clone();
+                    for (value, point) in simplex[1..].iter_mut() {
+                        point
+                            .iter_mut()
+                            .zip(&best)
+                            .for_each(|(p, b)| *p = b + 0.5 * (*p - b));
+                        *value = self.value(point);
+                    }
+                    watch.add_nodes(n);
+                }
+            }
+
+            simplex.sort_by(|a, b| a.0.total_cmp(&b.0));
+            history.push(simplex[0].0);
+        }
+
+        x.copy_from_slice(&simplex[0].1);
+        (simplex[0].0, watch.stats())
+    }
+
+    /// Quasi-Newton method keeping a dense approximation of the inverse Hessian
+    /// - tolerance: Stop when the norm of the gradient is lower
+    fn bfgs(
+        &self,
+        x: &mut [f64],
+        tolerance: f64,
+        budget: &Budget,
+        history: &mut Vec<f64>,
+    ) -> (f64, BudgetStats) {
+        let n = x.len();
+        let mut watch = budget.start();
+        history.clear();
+
+        let identity = (0..n * n)
+            .map(|i| if i % (n + 1) == 0 { 1. } else { 0. })
+            .collect::<Vec<_>>();
+        let mut h = identity.clone();
+        let mut value = self.value(x);
+        let mut gradient = vec![0.; n];
+        self.gradient(x, &mut gradient);
+        watch.add_nodes(2);
+        history.push(value);
+
+        let mut direction = vec![0.; n];
+        let mut next = vec![0.; n];
+        let mut next_gradient = vec![0.; n];
+        let (mut s, mut y, mut hy) = (vec![0.; n], vec![0.; n], vec![0.; n]);
+
+        while norm(&gradient) >= tolerance && watch.next_iteration() {
+            // d = -H g
+            for i in 0..n {
+                direction[i] = -VectorOp::dot(&h[i * n..(i + 1) * n], &gradient);
+            }
+            let (search, calls) = self.line_search(x, value, &gradient, &direction, &mut next);
+            watch.add_nodes(calls);
+            let Some((_, next_value)) = search else {
+                if h == identity {
+                    // No progress along the gradient
+                    break;
+                }
+                h.copy_from_slice(&identity);
+                continue;
+            };
+
+            self.gradient(&next, &mut next_gradient);
+            watch.add_nodes(1);
+            s.copy_from_slice(&next);
+            VectorOp::sub_assign(&mut s, x);
+            y.copy_from_slice(&next_gradient);
+            VectorOp::sub_assign(&mut y, &gradient);
+
+            // Skip the update if the curvature is not positive
+            let sy = VectorOp::dot(&s, &y);
+            if sy > 1e-12 {
+                let rho = 1. / sy;
+                for i in 0..n {
+                    hy[i] = VectorOp::dot(&h[i * n..(i + 1) * n], &y);
+                }
+                let yhy = VectorOp::dot(&y, &hy);
+                for i in 0..n {
+                    for j in 0..n {
+                        h[i * n + j] += -rho * (hy[i] * s[j] + s[i] * hy[j])
+                            + (rho * rho * yhy + rho) * s[i] * s[j];
+                    }
+                }
+            }
+
+            x.copy_from_slice(&next);
+            gradient.copy_from_slice(&next_gradient);
+            value = next_value;
+            history.push(value);
+        }
+
+        (value, watch.stats())
+    }
+
+    /// BFGS keeping only the last `memory` steps instead of the inverse Hessian
+    fn lbfgs(
+        &self,
+        x: &mut [f64],
+        memory: usize,
+        tolerance: f64,
+        budget: &Budget,
+        history: &mut Vec<f64>,
+    ) -> (f64, BudgetStats) {
+        let n = x.len();
+        let mut watch = budget.start();
+        history.clear();
+
+        let mut value = self.value(x);
+        let mut gradient = vec![0.; n];
+        self.gradient(x, &mut gradient);
+        watch.add_nodes(2);
+        history.push(value);
+
+        // (s, y, 1 / s.y) of the last steps, the most recent last
+        let mut steps: VecDeque<(Vec<f64>, Vec<f64>, f64)> = VecDeque::with_capacity(memory);
+        let mut alpha = vec![0.; memory];
+        let mut direction = vec![0.; n];
+        let mut next = vec![0.; n];
+        let mut next_gradient = vec![0.; n];
+
+        while norm(&gradient) >= tolerance && watch.next_iteration() {
+            // Two-loop recursion, d = -H g
+            direction.copy_from_slice(&gradient);
+            for (i, (s, y, rho)) in steps.iter().enumerate().rev() {
+                alpha[i] = rho * VectorOp::dot(s, &direction);
+                add_scaled(&mut direction, -alpha[i], y);
+            }
+            if let Some((s, y, _)) = steps.back() {
+                VectorOp::mul_assign_with(
+                    &mut direction,
+                    VectorOp::dot(s, y) / VectorOp::dot(y, y),
+                );
+            }
+            for (i, (s, y, rho)) in steps.iter().enumerate() {
+                let beta = rho * VectorOp::dot(y, &direction);
+                add_scaled(&mut direction, alpha[i] - beta, s);
+            }
+            VectorOp::mul_assign_with(&mut direction, -1.);
+
+            let (search, calls) = self.line_search(x, value, &gradient, &direction, &mut next);
+            watch.add_nodes(calls);
+            let Some((_, next_value)) = search else {
+                if steps.is_empty() {
+                    // No progress along the gradient
+                    break;
+                }
+                steps.clear();
+                continue;
+            };
+
+            self.gradient(&next, &mut next_gradient);
+            watch.add_nodes(1);
+            let mut s = next.clone();
+            VectorOp::sub_assign(&mut s, x);
+            let mut y = next_gradient.clone();
+            VectorOp::sub_assign(&mut y, &gradient);
+
+            let sy = VectorOp::dot(&s, &y);
+            if sy > 1e-12 && memory > 0 {
+                if steps.len() == memory {
+                    steps.pop_front();
+                }
+                steps.push_back((s, y, 1. / sy));
+            }
+
+            x.copy_from_slice(&next);
+            gradient.copy_from_slice(&next_gradient);
+            value = next_value;
+            history.push(value);
+        }
+
+        (value, watch.stats())
+    }
+
+    /// Covariance matrix adaptation evolution strategy
+    /// - sigma: Initial step size
+    /// - tolerance: Stop when the step size along the largest axis is lower
+    ///
+    /// The population size is 4 + 3 ln(n), the parameters follow Hansen's tutorial.
+    fn cma_es(
+        &self,
+        rng: &mut ThreadRng,
+        x: &mut [f64],
+        sigma: f64,
+        tolerance: f64,
+        budget: &Budget,
+        history: &mut Vec<f64>,
+    ) -> (f64, BudgetStats) {
+        let n = x.len();
+        let nf = n as f64;
+        let mut watch = budget.start();
+        history.clear();
+
+        // Selection & adaptation parameters
+        let lambda = 4 + (3. * nf.ln()).floor() as usize;
+        let mu = lambda / 2;
+        let mut weights = (0..mu)
+            .map(|i| (mu as f64 + 0.5).ln() - (i as f64 + 1.).ln())
+            .collect::<Vec<_>>();
+        let sum = VectorOp::sum(&weights);
+        VectorOp::mul_assign_with(&mut weights, 1. / sum);
+        let mueff = 1. / VectorOp::dot(&weights, &weights);
+
+        let cc = (4. + mueff / nf) / (nf + 4. + 2. * mueff / nf);
+        let cs = (mueff + 2.) / (nf + mueff + 5.);
+        let c1 = 2. / ((nf + 1.3).powi(2) + mueff);
+        let cmu = (1. - c1).min(2. * (mueff - 2. + 1. / mueff) / ((nf + 2.).powi(2) + mueff));
+        let damps = 1. + 2. * (((mueff - 1.) / (nf + 1.)).sqrt() - 1.).max(0.) + cs;
+        let chi_n = nf.sqrt() * (1. - 1. / (4. * nf) + 1. / (21. * nf * nf));
+
+        let mut sigma = sigma;
+        let mut mean = x.to_vec();
+        let mut best = (self.value(x), x.to_vec());
+        watch.add_nodes(1);
+        history.push(best.0);
+
+        let mut c = (0..n * n)
+            .map(|i| if i % (n + 1) == 0 { 1. } else { 0. })
+            .collect::<Vec<_>>();
+        let (mut b, mut d) = (c.clone(), vec![1.; n]);
+        let (mut pc, mut ps) = (vec![0.; n], vec![0.; n]);
+        let mut population = vec![(0., vec![0.; n], vec![0.; n]); lambda];
+        let (mut z, mut step) = (vec![0.; n], vec![0.; n]);
+        let mut generation = 0;
+
+        while sigma * d.iter().fold(0., |a: f64, b| a.max(*b)) >= tolerance
+            && watch.next_iteration()
+        {
+            // Sample y = B D z, x = mean + sigma y
+            for (value, point, y) in population.iter_mut() {
+                z.iter_mut().for_each(|zi| *zi = StandardNormal.sample(rng));
+                for i in 0..n {
+                    y[i] = (0..n).map(|j| b[i * n + j] * d[j] * z[j]).sum();
+                }
+                point.copy_from_slice(&mean);
+                add_scaled(point, sigma, y);
+                *value = self.value(point);
+            }
+            watch.add_nodes(lambda);
+            population.sort_by(|a, b| a.0.total_cmp(&b.0));
+            if population[0].0 < best.0 {
+                best = (population[0].0, population[0].1.clone());
+            }
+
+            // Weighted mean of the best steps
+            step.iter_mut().for_each(|s| *s = 0.);
+            for (w, (_, _, y)) in weights.iter().zip(&population) {
+                add_scaled(&mut step, *w, y);
+            }
+            add_scaled(&mut mean, sigma, &step);
+
+            // Evolution paths, C^-1/2 = B D^-1 B^T
+            let mut bt_step = vec![0.; n];
+            for j in 0..n {
+                bt_step[j] = (0..n).map(|i| b[i * n + j] * step[i]).sum::<f64>() / d[j];
+            }
+            let k = (cs * (2. - cs) * mueff).sqrt();
+            for i in 0..n {
+                let white = (0..n).map(|j| b[i * n + j] * bt_step[j]).sum::<f64>();
+                ps[i] = (1. - cs) * ps[i] + k * white;
+            }
+            generation += 1;
+            let ps_norm = norm(&ps);
+            let hsig = ps_norm / (1. - (1. - cs).powi(2 * generation)).sqrt() / chi_n
+                < 1.4 + 2. / (nf + 1.);
+            let hsig = if hsig { 1. } else { 0. };
+            let k = (cc * (2. - cc) * mueff).sqrt();
+            pc.iter_mut()
+                .zip(&step)
+                .for_each(|(p, s)| *p = (1. - cc) * *p + hsig * k * s);
+
+            // Covariance: rank-one & rank-mu updates
+            let correction = (1. - hsig) * cc * (2. - cc);
+            for i in 0..n {
+                for j in 0..n {
+                    let rank_mu = weights
+                        .iter()
+                        .zip(&population)
+                        .map(|(w, (_, _, y))| w * y[i] * y[j])
+                        .sum::<f64>();
+                    c[i * n + j] = (1. - c1 - cmu) * c[i * n + j]
+                        + c1 * (pc[i] * pc[j] + correction * c[i * n + j])
+                        + cmu * rank_mu;
+                }
+            }
+            sigma *= ((cs / damps) * (ps_norm / chi_n - 1.)).exp();
+
+            let (eigenvalues, eigenvectors) = jacobi(c.clone(), n);
+            d.iter_mut()
+                .zip(eigenvalues)
+                .for_each(|(di, e)| *di = e.max(1e-20).sqrt());
+            b = eigenvectors;
+            history.push(best.0);
+        }
+
+        x.copy_from_slice(&best.1);
+        (best.0, watch.stats())
+    }
+}
+
+impl<F: Fn(&[f64]) -> f64> Objective for F {
+    fn value(&self, x: &[f64]) -> f64 {
+        self(x)
+    }
+}
+
+#[test]
+fn continuous() {
+    let rosenbrock = |x: &[f64]| (1. - x[0]).powi(2) + 100. * (x[1] - x[0] * x[0]).powi(2);
+    let budget = Budget::iterations(5000);
+    let mut history = Vec::new();
+    let close = |x: &[f64]| x.iter().all(|xi| (xi - 1.).abs() < 1e-3);
+
+    let mut x = [-1.2, 1.];
+    let (value, stats) = rosenbrock.nelder_mead(&mut x, 0.5, 1e-14, &budget, &mut history);
+    assert!(value < 1e-6 && close(&x));
+    assert_eq!(stats.stop, None);
+    assert_eq!(history.len(), stats.iterations + 1);
+    assert!(history.windows(2).all(|w| w[1] <= w[0]));
+
+    let mut x = vec![-1.2, 1.];
+    let (value, stats) = rosenbrock.bfgs(&mut x, 1e-6, &budget, &mut history);
+    assert!(value < 1e-8 && close(&x));
+    assert_eq!(stats.stop, None);
+
+    let mut x = [-1.2, 1.];
+    let (value, _) = rosenbrock.lbfgs(&mut x, 5, 1e-6, &budget, &mut history);
+    assert!(value < 1e-8 && close(&x));
+
+    // Every call to `value` or `gradient` is a node, the line searches included
+    struct Counted(std::cell::Cell<usize>);
+    impl Objective for Counted {
+        fn value(&self, x: &[f64]) -> f64 {
+            self.0.set(self.0.get() + 1);
+            (1. - x[0]).powi(2) + 100. * (x[1] - x[0] * x[0]).powi(2)
+        }
+
+        fn gradient(&self, x: &[f64], gradient: &mut [f64]) {
+            self.0.set(self.0.get() + 1);
+            gradient[0] = -2. * (1. - x[0]) - 400. * x[0] * (x[1] - x[0] * x[0]);
+            gradient[1] = 200. * (x[1] - x[0] * x[0]);
+        }
+    }
+    let counted = Counted(Default::default());
+    let (_, stats) = counted.bfgs(&mut [-1.2, 1.], 1e-6, &budget, &mut history);
+    assert_eq!(stats.nodes, counted.0.take());
+    let (_, stats) = counted.lbfgs(&mut [-1.2, 1.], 5, 1e-6, &budget, &mut history);
+    assert_eq!(stats.nodes, counted.0.take());
+
+    let mut rng = rand::thread_rng();
+    let mut x = [-1.2, 1.];
+    let (value, stats) = rosenbrock.cma_es(&mut rng, &mut x, 0.5, 1e-8, &budget, &mut history);
+    assert!(value < 1e-8 && close(&x));
+    assert_eq!(stats.stop, None);
+}
@@ -0,0 +1,526 @@
+use crate::{Budget, BudgetStats, Game, Loss, NN};
+use rand::{
+    prelude::{SliceRandom, ThreadRng},
+    Rng,
+};
+
+/// Move of a player stored in the replay buffer
+/// - legal: Legal actions of the next state
+/// - done: The next state is terminal, the reward is the outcome of the game
+/// - opponent: The next state is played by an opponent, its value is negated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition<const IN: usize, const OUT: usize> {
+    pub state: [f32; IN],
+    pub action: usize,
+    pub reward: f32,
+    pub next: [f32; IN],
+    pub legal: [bool; OUT],
+    pub done: bool,
+    pub opponent: bool,
+}
+
+/// Sampling of the replay buffer
+/// - Prioritized: Proportional to |TD error|^alpha, corrected by importance weights
+///   with exponent beta
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Sampling {
+    #[default]
+    Uniform,
+    Prioritized {
+        alpha: f32,
+        beta: f32,
+    },
+}
+
+/// Ring buffer of the last `capacity` transitions, the priorities are kept in a sum tree
+#[derive(Debug, Clone)]
+pub struct ReplayBuffer<const IN: usize, const OUT: usize> {
+    capacity: usize,
+    sampling: Sampling,
+    transitions: Vec<Transition<IN, OUT>>,
+    next: usize,
+    /// Leaves from index `leaves`, each node is the sum of its children
+    tree: Vec<f32>,
+    leaves: usize,
+    max_priority: f32,
+}
+
+impl<const IN: usize, const OUT: usize> ReplayBuffer<IN, OUT> {
+    /// The capacity is at least 1
+    pub fn new(capacity: usize, sampling: Sampling) -> Self {
+        let capacity = capacity.max(1);
+        let leaves = capacity.next_power_of_two();
+        Self {
+            capacity,
+            sampling,
+            transitions: Vec::with_capacity(capacity),
+            next: 0,
+            tree: vec![0.; 2 * leaves],
+            leaves,
+            max_priority: 1.,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> &Transition<IN, OUT> {
+        &self.transitions[i]
+    }
+
+    fn set_priority(&mut self, i: usize, priority: f32) {
+        let mut node = i + self.leaves;
+        self.tree[node] = priority;
+        while node > 1 {
+            node /= 2;
+            self.tree[node] = self.tree[2 * node] + self.tree[2 * node + 1];
+        }
+    }
+
+    /// New transitions get the highest priority seen, the oldest one is replaced when full
+    pub fn push(&mut self, transition: Transition<IN, OUT>) {
+        let i = self.next;
+        if self.transitions.len() < self.capacity {
+            self.transitions.push(transition);
+        } else {
+            self.transitions[i] = transition;
+        }
+        self.next = (self.next + 1) % self.capacity;
+        self.set_priority(i, self.max_priority);
+    }
+
+    /// Draw `batch` transitions, `weights` receives their importance weights, 1 if uniform
+    pub fn sample(
+        &self,
+        rng: &mut ThreadRng,
+        batch: usize,
+        indices: &mut Vec<usize>,
+        weights: &mut Vec<f32>,
+    ) {
+        indices.clear();
+        weights.clear();
+        let len = self.len();
+
+        match self.sampling {
+            Sampling::Uniform => {
+                indices.extend((0..batch).map(|_| rng.gen_range(0..len)));
+                weights.resize(batch, 1.);
+            }
+            Sampling::Prioritized { beta, .. } => {
+                let total = self.tree[1];
+                for _ in 0..batch {
+                    let mut x = rng.gen_range(0f32..total);
+                    let mut node = 1;
+                    while node < self.leaves {
+                        node *= 2;
+                        if x >= self.tree[node] {
+                            x -= self.tree[node];
+                            node += 1;
+                        }
+                    }
+                    // Rounding may reach an empty leaf
+                    let i = (node - self.leaves).min(len - 1);
+                    indices.push(i);
+                    let p = self.tree[i + self.leaves] / total;
+                    weights.push((len as f32 * p).powf(-beta));
+                }
+                let max = weights.iter().copied().fold(0., f32::max);
+                weights.iter_mut().for_each(|w| *w /= max);
+            }
+        }
+    }
+
+    /// Update the priorities of sampled transitions from their TD errors, nothing if uniform
+    pub fn update_priorities(&mut self, indices: &[usize], errors: &[f32]) {
+        if let Sampling::Prioritized { alpha, .. } = self.sampling {
+            for (&i, error) in indices.iter().zip(errors) {
+                let priority = (error.abs() + 1e-3).powf(alpha);
+                self.max_priority = self.max_priority.max(priority);
+                self.set_priority(i, priority);
+            }
+        }
+    }
+}
+
+/// Exploration rate along the moves played
+/// - Linear: From start to end in `steps` moves, then end
+/// - Exponential: From start to end, the gap is multiplied by decay at each move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Epsilon {
+    Constant(f32),
+    Linear { start: f32, end: f32, steps: usize },
+    Exponential { start: f32, end: f32, decay: f32 },
+}
+
+impl Default for Epsilon {
+    fn default() -> Self {
+        Self::Linear {
+            start: 1.,
+            end: 0.05,
+            steps: 10_000,
+        }
+    }
+}
+
+impl Epsilon {
+    pub fn value(&self, step: usize) -> f32 {
+        match *self {
+            Epsilon::Constant(eps) => eps,
+            Epsilon::Linear { start, end, steps } => {
+                start + (end - start) * (step as f32 / steps as f32).min(1.)
+            }
+            Epsilon::Exponential { start, end, decay } => {
+                end + (start - end) * decay.powf(step as f32)
+            }
+        }
+    }
+}
+
+/// - discount: Weight of the value of the next state
+/// - epsilon: Probability of a random move during training
+/// - batch_size: Transitions per gradient step
+/// - capacity: Size of the replay buffer
+/// - warmup: Transitions collected before the first gradient step
+/// - train_every: Moves between gradient steps, 0 to never train
+/// - target_sync: Gradient steps between copies of the online network into the target one,
+///   0 to use the online network for the targets
+/// - double: Double DQN, the online network picks the next action and the target one
+///   evaluates it
+/// - loss: Loss of the Q value against its target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DqnConfig {
+    pub discount: f32,
+    pub epsilon: Epsilon,
+    pub batch_size: usize,
+    pub capacity: usize,
+    pub sampling: Sampling,
+    pub warmup: usize,
+    pub train_every: usize,
+    pub target_sync: usize,
+    pub double: bool,
+    pub loss: Loss,
+}
+
+impl Default for DqnConfig {
+    fn default() -> Self {
+        Self {
+            discount: 0.99,
+            epsilon: Epsilon::default(),
+            batch_size: 32,
+            capacity: 10_000,
+            sampling: Sampling::default(),
+            warmup: 100,
+            train_every: 1,
+            target_sync: 500,
+            double: true,
+            loss: Loss::Huber { delta: 1. },
+        }
+    }
+}
+
+impl DqnConfig {
+    pub fn with_discount(mut self, discount: f32) -> Self {
+        self.discount = discount;
+        self
+    }
+
+    pub fn with_epsilon(mut self, epsilon: Epsilon) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn with_warmup(mut self, warmup: usize) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    pub fn with_train_every(mut self, train_every: usize) -> Self {
+        self.train_every = train_every;
+        self
+    }
+
+    pub fn with_target_sync(mut self, target_sync: usize) -> Self {
+        self.target_sync = target_sync;
+        self
+    }
+
+    pub fn with_double(mut self, double: bool) -> Self {
+        self.double = double;
+        self
+    }
+
+    pub fn with_loss(mut self, loss: Loss) -> Self {
+        self.loss = loss;
+        self
+    }
+}
+
+fn argmax_legal<const OUT: usize>(q: &[f32; OUT], legal: &[bool; OUT]) -> usize {
+    (0..OUT)
+        .filter(|&a| legal[a])
+        .max_by(|&a, &b| q[a].total_cmp(&q[b]))
+        .unwrap_or(0)
+}
+
+/// Reward of a finished game for `player`, against the next player if there are several
+fn zero_sum<G: Game>(game: &G, player: usize) -> f32 {
+    if G::PLAYERS == 1 {
+        game.reward(player)
+    } else {
+        game.reward(player) - game.reward((player + 1) % G::PLAYERS)
+    }
+}
+
+/// Deep Q-learning with an online and a target network
+///
+/// The outputs are the Q values of the actions, `Game::input` is seen from the player to
+/// move so that every player shares the network.
+#[derive(Debug, Clone)]
+pub struct Dqn<const IN: usize, const OUT: usize> {
+    pub online: NN<IN, OUT>,
+    pub target: NN<IN, OUT>,
+    pub buffer: ReplayBuffer<IN, OUT>,
+    pub config: DqnConfig,
+    steps: usize,
+    updates: usize,
+}
+
+impl<const IN: usize, const OUT: usize> Dqn<IN, OUT> {
+    pub fn new(online: NN<IN, OUT>, config: DqnConfig) -> Self {
+        Self {
+            target: online.clone(),
+            online,
+            buffer: ReplayBuffer::new(config.capacity, config.sampling),
+            config,
+            steps: 0,
+            updates: 0,
+        }
+    }
+
+    /// Exploration rate of the next move
+    pub fn epsilon(&self) -> f32 {
+        self.config.epsilon.value(self.steps)
+    }
+
+    /// Target of the Q value of a transition
+    fn target_value(&mut self, t: &Transition<IN, OUT>) -> f32 {
+        if t.done {
+            return t.reward;
+        }
+        let target = if self.config.target_sync > 0 {
+            self.target.predict(t.next)
+        } else {
+            self.online.predict(t.next)
+        };
+        let action = if self.config.double {
+            argmax_legal(&self.online.predict(t.next), &t.legal)
+        } else {
+            argmax_legal(&target, &t.legal)
+        };
+        let sign = if t.opponent { -1. } else { 1. };
+        t.reward + sign * self.config.discount * target[action]
+    }
+
+    /// One gradient step on a sampled batch, return its mean loss
+    pub fn learn(&mut self, rng: &mut ThreadRng) -> f32 {
+        let batch = self.config.batch_size;
+        let (mut indices, mut weights) = (Vec::new(), Vec::new());
+        self.buffer.sample(rng, batch, &mut indices, &mut weights);
+
+        let mut errors = Vec::with_capacity(batch);
+        let mut total = 0.;
+        for (&i, &w) in indices.iter().zip(&weights) {
+            let t = *self.buffer.get(i);
+            let y = self.target_value(&t);
+            let q = self.online.predict(t.state)[t.action];
+
+            let mut g = [0.];
+            total += w * self.config.loss.eval(&[q], &[y], &mut g);
+            let mut grad = [0.; OUT];
+            grad[t.action] = w * g[0] / batch as f32;
+            self.online.backprop(grad);
+            errors.push(q - y);
+        }
+        self.online.step();
+        self.buffer.update_priorities(&indices, &errors);
+
+        self.updates += 1;
+        if self.updates.is_multiple_of(self.config.target_sync) {
+            self.target.layers.clone_from(&self.online.layers);
+        }
+        total / batch as f32
+    }
+
+    /// Epsilon-greedy self-play, every move is stored and trained on
+    ///
+    /// One iteration is one game and one node is one move. Return the mean loss.
+    pub fn train<G: Game>(
+        &mut self,
+        rng: &mut ThreadRng,
+        budget: &Budget,
+        game: &G,
+        actions: &mut Vec<usize>,
+    ) -> (f32, BudgetStats) {
+        let mut watch = budget.start();
+        let (mut total, mut n) = (0., 0);
+
+        while watch.next_iteration() {
+            let mut game = game.clone();
+            game.fill(actions);
+
+            while !actions.is_empty() {
+                let player = game.turn() % G::PLAYERS;
+                let state = game.input();
+                let action = if rng.gen_range(0f32..1.) < self.epsilon() {
+                    *actions.choose(rng).unwrap()
+                } else {
+                    self.online.best_action(state, actions).unwrap()
+                };
+
+                game.update(action);
+                game.fill(actions);
+                let mut legal = [false; OUT];
+                actions.iter().for_each(|&a| legal[a] = true);
+                let done = actions.is_empty();
+
+                self.buffer.push(Transition {
+                    state,
+                    action,
+                    reward: if done { zero_sum(&game, player) } else { 0. },
+                    next: game.input(),
+                    legal,
+                    done,
+                    opponent: game.turn() % G::PLAYERS != player,
+                });
+                self.steps += 1;
+                watch.add_nodes(1);
+
+                if self.buffer.len() >= self.config.warmup.max(self.config.batch_size)
+                    && self.steps.is_multiple_of(self.config.train_every)
+                {
+                    total += self.learn(rng);
+                    n += 1;
+                }
+            }
+        }
+
+        (total / n.max(1) as f32, watch.stats())
+    }
+}
+
+#[test]
+fn dqn() {
+    use crate::{outcome, Activation, Optimizer, OptimizerConfig, Status};
+
+    /// Take 1 to 3 stones, the player taking the last one wins
+    #[derive(Debug, Clone)]
+    struct Nim {
+        stones: usize,
+        turn: usize,
+    }
+
+    impl Game for Nim {
+        fn input<const N: usize>(&self) -> [f32; N] {
+            let mut input = [0.; N];
+            input[self.stones] = 1.;
+            input
+        }
+        fn turn(&self) -> usize {
+            self.turn
+        }
+        fn status(&self) -> Status {
+            if self.stones == 0 {
+                Status::Win((self.turn + 1) % 2)
+            } else {
+                Status::None
+            }
+        }
+        fn reward(&self, player: usize) -> f32 {
+            outcome(self.status(), player) as f32
+        }
+        fn fill(&self, actions: &mut Vec<usize>) {
+            actions.clear();
+            actions.extend((1..=3).filter(|&n| n <= self.stones));
+        }
+        fn update(&mut self, action: usize) {
+            self.stones -= action;
+            self.turn += 1;
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let nn = NN::<8, 4>::builder()
+        .dense(32, Activation::Relu)
+        .output(Activation::Linear)
+        .with_optimizer(OptimizerConfig::new(Optimizer::adam(), 0.005));
+    let config = DqnConfig::default()
+        .with_discount(1.)
+        .with_epsilon(Epsilon::Constant(0.3))
+        .with_batch_size(16)
+        .with_warmup(64)
+        .with_train_every(2)
+        .with_target_sync(50)
+        .with_sampling(Sampling::Prioritized {
+            alpha: 0.6,
+            beta: 0.4,
+        });
+    let mut dqn = Dqn::new(nn, config);
+    let mut actions = Vec::new();
+    let game = Nim { stones: 7, turn: 0 };
+    let (_, stats) = dqn.train(&mut rng, &Budget::iterations(1500), &game, &mut actions);
+    assert_eq!(stats.iterations, 1500);
+
+    // Taking the last stones wins at once
+    for stones in [1, 2, 3] {
+        let game = Nim { stones, turn: 0 };
+        game.fill(&mut actions);
+        let best = dqn.online.best_action(game.input(), &actions);
+        assert_eq!(best, Some(stones), "{stones}");
+    }
+
+    // Random play scores 0.5, perfect play wins every game it starts
+    let (stats, _) = crate::arena(
+        &mut rng,
+        &Budget::iterations(200),
+        &game,
+        &mut dqn,
+        &mut crate::RandomAgent,
+        &mut actions,
+    );
+    assert!(stats.score() > 0.65, "{stats:?}");
+
+    let mut buffer = ReplayBuffer::<1, 1>::new(0, Sampling::Uniform);
+    for action in 0..2 {
+        buffer.push(Transition {
+            state: [0.],
+            action,
+            reward: 0.,
+            next: [0.],
+            legal: [true],
+            done: true,
+            opponent: false,
+        });
+    }
+    assert_eq!(buffer.len(), 1);
+    assert_eq!(buffer.get(0).action, 1);
+}
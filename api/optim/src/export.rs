@@ -0,0 +1,512 @@
+use crate::{Activation, NN};
+use std::fmt::Write;
+
+/// Storage of the weights in the exported code, the bias is always f32
+/// - F32: Exact weights
+/// - I16, I8: Symmetric quantisation with one scale per row, the largest weight of a row
+///   maps to the largest integer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quantization {
+    #[default]
+    F32,
+    I16,
+    I8,
+}
+
+impl Quantization {
+    fn max(&self) -> f32 {
+        match self {
+            Quantization::F32 => 1.,
+            Quantization::I16 => i16::MAX as f32,
+            Quantization::I8 => i8::MAX as f32,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Quantization::F32 => "f32",
+            Quantization::I16 => "i16",
+            Quantization::I8 => "i8",
+        }
+    }
+}
+
+/// - module: Name of the generated module
+/// - quantization: Storage of the weights
+/// - base64: Weights and biases are packed in base64 strings, decoded by `Net::new`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportConfig {
+    pub module: String,
+    pub quantization: Quantization,
+    pub base64: bool,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            module: "nn".to_string(),
+            quantization: Quantization::default(),
+            base64: false,
+        }
+    }
+}
+
+impl ExportConfig {
+    pub fn with_module(mut self, module: &str) -> Self {
+        self.module = module.to_string();
+        self
+    }
+
+    pub fn with_quantization(mut self, quantization: Quantization) -> Self {
+        self.quantization = quantization;
+        self
+    }
+
+    pub fn with_base64(mut self, base64: bool) -> Self {
+        self.base64 = base64;
+        self
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard alphabet without padding
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Scale of each row and the quantised weights
+fn quantize(weights: &[f32], inputs: usize, max: f32) -> (Vec<f32>, Vec<i32>) {
+    let mut scales = Vec::new();
+    let mut quantized = Vec::with_capacity(weights.len());
+    for row in weights.chunks(inputs) {
+        let largest = row.iter().fold(0f32, |m, w| m.max(w.abs()));
+        let scale = if largest > 0. { largest / max } else { 1. };
+        scales.push(scale);
+        quantized.extend(
+            row.iter()
+                .map(|w| (w / scale).round().clamp(-max, max) as i32),
+        );
+    }
+    (scales, quantized)
+}
+
+fn activation_name(activation: Activation) -> Option<&'static str> {
+    match activation {
+        Activation::Linear => None,
+        Activation::Relu => Some("relu"),
+        Activation::Tanh => Some("tanh"),
+        Activation::Sigmoid => Some("sigmoid"),
+        Activation::Softmax => Some("softmax"),
+    }
+}
+
+/// Same operations in the same order as `Activation::apply`
+fn activation_code(activation: Activation) -> &'static str {
+    match activation {
+        Activation::Linear => "",
+        Activation::Relu => {
+            "
+    fn relu(x: &mut [f32]) {
+        x.iter_mut().for_each(|x| *x = x.max(0.));
+    }
+"
+        }
+        Activation::Tanh => {
+            "
+    fn tanh(x: &mut [f32]) {
+        x.iter_mut().for_each(|x| *x = x.tanh());
+    }
+"
+        }
+        Activation::Sigmoid => {
+            "
+    fn sigmoid(x: &mut [f32]) {
+        x.iter_mut().for_each(|x| *x = 1. / (1. + (-*x).exp()));
+    }
+"
+        }
+        Activation::Softmax => {
+            "
+    fn softmax(x: &mut [f32]) {
+        let max = x.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        x.iter_mut().for_each(|x| *x = (*x - max).exp());
+        let c = 1. / x.iter().sum::<f32>();
+        x.iter_mut().for_each(|x| *x *= c);
+    }
+"
+        }
+    }
+}
+
+/// Same operations in the same order as `Dense::forward` and `Backend::dot`
+const DENSE: &str = "
+    fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let split = a.len() - a.len() % 8;
+        let mut acc = [0f32; 8];
+        for (a, b) in a[..split].chunks_exact(8).zip(b[..split].chunks_exact(8)) {
+            for k in 0..8 {
+                acc[k] += a[k] * b[k];
+            }
+        }
+        let mut out = ((acc[0] + acc[4]) + (acc[1] + acc[5])) + ((acc[2] + acc[6]) + (acc[3] + acc[7]));
+        for (x, y) in a[split..].iter().zip(&b[split..]) {
+            out += x * y;
+        }
+        out
+    }
+
+    fn dense(weights: &[f32], bias: &[f32], input: &[f32]) -> Vec<f32> {
+        bias.iter()
+            .zip(weights.chunks(input.len()))
+            .map(|(b, row)| dot(row, input) + b)
+            .collect()
+    }
+";
+
+const NORMALIZE: &str = "
+    fn normalize(x: &mut [f32]) {
+        let n = x.len() as f32;
+        let mut sum = 0.;
+        for xi in x.iter() {
+            sum += xi;
+        }
+        let mean = sum / n;
+        x.iter_mut().for_each(|xi| *xi -= mean);
+        let sigma = (dot(x, x) / n).sqrt();
+        let scale = 1. / (sigma + 1e-8);
+        x.iter_mut().for_each(|xi| *xi *= scale);
+    }
+";
+
+const DECODE: &str = "
+    fn decode(s: &str) -> Vec<u8> {
+        let mut out = Vec::with_capacity(s.len() * 3 / 4);
+        let (mut acc, mut bits) = (0u32, 0);
+        for c in s.bytes() {
+            let v = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => continue,
+            };
+            acc = (acc << 6 | v as u32) & 0xffffff;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((acc >> bits) as u8);
+            }
+        }
+        out
+    }
+
+    fn f32s(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    }
+";
+
+const DECODE_I16: &str = "
+    fn i16s(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32)
+            .collect()
+    }
+";
+
+const DECODE_I8: &str = "
+    fn i8s(bytes: &[u8]) -> Vec<f32> {
+        bytes.iter().map(|&b| b as i8 as f32).collect()
+    }
+";
+
+const DEQUANTIZE: &str = "
+    fn dequantize(quantized: &[f32], scales: &[f32]) -> Vec<f32> {
+        let inputs = quantized.len() / scales.len();
+        quantized
+            .chunks(inputs)
+            .zip(scales)
+            .flat_map(|(row, scale)| row.iter().map(move |q| q * scale))
+            .collect()
+    }
+";
+
+/// Standalone Rust code of the inference, without dependency
+///
+/// The module holds the weights as constants and `Net`, whose `predict` and `forward` match
+/// the ones of `NN` exactly in F32. It fits in a single file submission.
+impl<const IN: usize, const OUT: usize> NN<IN, OUT> {
+    pub fn export(&self, config: &ExportConfig) -> String {
+        let mut out = String::new();
+        self.write_module(config, &mut out).unwrap();
+        out
+    }
+
+    pub fn export_file(&self, config: &ExportConfig, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.export(config))
+    }
+
+    fn write_module(&self, config: &ExportConfig, f: &mut String) -> std::fmt::Result {
+        let quantization = config.quantization;
+        let quantized = quantization != Quantization::F32;
+        let architecture = self
+            .layers
+            .iter()
+            .map(|layer| {
+                let normalize = if layer.normalize { "normalize, " } else { "" };
+                format!("{normalize}{} {:?}", layer.outputs, layer.activation)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(f, "/// NN<{IN}, {OUT}>: {architecture}")?;
+        writeln!(f, "/// Weights in {}", quantization.name())?;
+        writeln!(f, "pub mod {} {{", config.module)?;
+        writeln!(f, "    pub const IN: usize = {IN};")?;
+        writeln!(f, "    pub const OUT: usize = {OUT};")?;
+
+        // Constants and their decoding in `Net::new`
+        let mut weights = Vec::new();
+        let mut biases = Vec::new();
+        for (i, layer) in self.layers.iter().enumerate() {
+            writeln!(f)?;
+            let raw = if quantized {
+                let (scales, q) = quantize(&layer.weights, layer.inputs, quantization.max());
+                writeln!(f, "    const S{i}: [f32; {}] = {scales:?};", scales.len())?;
+                if config.base64 {
+                    let bytes = match quantization {
+                        Quantization::I16 => {
+                            q.iter().flat_map(|&q| (q as i16).to_le_bytes()).collect()
+                        }
+                        _ => q.iter().map(|&q| q as i8 as u8).collect::<Vec<_>>(),
+                    };
+                    writeln!(f, "    const W{i}: &str = \"{}\";", base64(&bytes))?;
+                    format!("{}s(&decode(W{i}))", quantization.name())
+                } else {
+                    let ty = quantization.name();
+                    writeln!(f, "    const W{i}: [{ty}; {}] = {q:?};", q.len())?;
+                    format!("W{i}.map(f32::from)")
+                }
+            } else if config.base64 {
+                let bytes = layer.weights.iter().flat_map(|w| w.to_le_bytes());
+                let bytes = bytes.collect::<Vec<_>>();
+                writeln!(f, "    const W{i}: &str = \"{}\";", base64(&bytes))?;
+                format!("f32s(&decode(W{i}))")
+            } else {
+                let n = layer.weights.len();
+                writeln!(f, "    const W{i}: [f32; {n}] = {:?};", layer.weights)?;
+                format!("W{i}.to_vec()")
+            };
+            weights.push(if quantized {
+                format!("dequantize(&{raw}, &S{i})")
+            } else {
+                raw
+            });
+
+            if config.base64 {
+                let bytes = layer.bias.iter().flat_map(|b| b.to_le_bytes());
+                let bytes = bytes.collect::<Vec<_>>();
+                writeln!(f, "    const B{i}: &str = \"{}\";", base64(&bytes))?;
+                biases.push(format!("f32s(&decode(B{i}))"));
+            } else {
+                let n = layer.bias.len();
+                writeln!(f, "    const B{i}: [f32; {n}] = {:?};", layer.bias)?;
+                biases.push(format!("B{i}.to_vec()"));
+            }
+        }
+
+        write!(
+            f,
+            "
+    pub struct Net {{
+        weights: Vec<Vec<f32>>,
+        bias: Vec<Vec<f32>>,
+    }}
+
+    impl Default for Net {{
+        fn default() -> Self {{
+            Self::new()
+        }}
+    }}
+
+    impl Net {{
+        pub fn new() -> Self {{
+            Self {{
+                weights: vec![{}],
+                bias: vec![{}],
+            }}
+        }}
+
+        pub fn predict(&self, input: [f32; IN]) -> [f32; OUT] {{
+            let mut x = input.to_vec();
+",
+            weights.join(", "),
+            biases.join(", ")
+        )?;
+        for (i, layer) in self.layers.iter().enumerate() {
+            if layer.normalize {
+                writeln!(f, "            normalize(&mut x);")?;
+            }
+            writeln!(
+                f,
+                "            x = dense(&self.weights[{i}], &self.bias[{i}], &x);"
+            )?;
+            if let Some(name) = activation_name(layer.activation) {
+                writeln!(f, "            {name}(&mut x);")?;
+            }
+        }
+        write!(
+            f,
+            "            let mut output = [0.; OUT];
+            output.copy_from_slice(&x);
+            output
+        }}
+
+        /// Index of the highest output
+        pub fn forward(&self, input: [f32; IN]) -> usize {{
+            self.predict(input)
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+                .unwrap()
+                .0
+        }}
+    }}
+"
+        )?;
+
+        // Only the functions which are used
+        f.push_str(DENSE);
+        if self.layers.iter().any(|layer| layer.normalize) {
+            f.push_str(NORMALIZE);
+        }
+        let activations = self.layers.iter().map(|l| l.activation).collect::<Vec<_>>();
+        for (i, &activation) in activations.iter().enumerate() {
+            if !activations[..i].contains(&activation) {
+                f.push_str(activation_code(activation));
+            }
+        }
+        if config.base64 {
+            f.push_str(DECODE);
+            match quantization {
+                Quantization::F32 => (),
+                Quantization::I16 => f.push_str(DECODE_I16),
+                Quantization::I8 => f.push_str(DECODE_I8),
+            }
+        }
+        if quantized {
+            f.push_str(DEQUANTIZE);
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[test]
+fn export() {
+    use std::process::Command;
+
+    let mut nn = crate::model::sample_nn();
+
+    let inputs = [
+        [0.5, -0.2, 0.3, 0.8, -1.],
+        [1., 2., 3., 4., 5.],
+        [-0.7, 0.1, 0.1, 0.4, 0.],
+    ];
+    let exports = [
+        ("exact", Quantization::F32, false),
+        ("exact_base64", Quantization::F32, true),
+        ("wide", Quantization::I16, false),
+        ("wide_base64", Quantization::I16, true),
+        ("narrow", Quantization::I8, false),
+        ("narrow_base64", Quantization::I8, true),
+    ];
+
+    // Every module prints the bits of its outputs and its action for each input
+    let mut program = String::new();
+    for (module, quantization, base64) in exports {
+        let config = ExportConfig::default()
+            .with_module(module)
+            .with_quantization(quantization)
+            .with_base64(base64);
+        program.push_str(&nn.export(&config));
+    }
+    program.push_str("\nfn main() {\n");
+    for (module, _, _) in exports {
+        program.push_str(&format!("    let net = {module}::Net::new();\n"));
+        for input in inputs {
+            program.push_str(&format!(
+                "    let output = net.predict({input:?});
+    let bits = output.iter().map(|x| x.to_bits().to_string()).collect::<Vec<_>>();
+    println!(\"{{}} {{}}\", bits.join(\" \"), net.forward({input:?}));\n"
+            ));
+        }
+    }
+    program.push_str("}\n");
+
+    let dir = std::env::temp_dir().join(format!("optim-export-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("main.rs");
+    let binary = dir.join("main");
+    std::fs::write(&source, &program).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or("rustc".to_string());
+    let compiled = Command::new(rustc)
+        .args(["--edition", "2021", "-D", "warnings", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .unwrap();
+    assert!(
+        compiled.status.success(),
+        "{}",
+        String::from_utf8_lossy(&compiled.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let stdout = String::from_utf8(run.stdout).unwrap();
+    let mut lines = stdout.lines();
+
+    for (module, quantization, _) in exports {
+        for input in inputs {
+            let mut values = lines.next().unwrap().split(' ');
+            let output = [(); 3].map(|_| f32::from_bits(values.next().unwrap().parse().unwrap()));
+            let action = values.next().unwrap().parse::<usize>().unwrap();
+            let expected = nn.predict(input);
+            let tolerance = match quantization {
+                Quantization::F32 => 0.,
+                Quantization::I16 => 1e-3,
+                Quantization::I8 => 5e-2,
+            };
+            for (a, b) in output.iter().zip(expected) {
+                assert!(
+                    (a - b).abs() <= tolerance,
+                    "{module} {output:?} {expected:?}"
+                );
+            }
+            if quantization == Quantization::F32 {
+                assert_eq!(action, nn.forward(input));
+            }
+        }
+    }
+
+    assert_eq!(base64(b"Man"), "TWFu");
+    assert_eq!(base64(b"Ma"), "TWE");
+    assert_eq!(base64(b"M"), "TQ");
+    let (scales, q) = quantize(&[0.5, -1., 0., 0., 0., 0.], 3, 127.);
+    assert_eq!(scales, [1. / 127., 1.]);
+    assert_eq!(q, [64, -127, 0, 0, 0, 0]);
+}
@@ -1,11 +1,9 @@
-use rand::{
-    prelude::{SliceRandom, ThreadRng},
-    Rng,
-};
-use std::{
-    fmt::Debug,
-    time::{Duration, Instant},
+use crate::{
+    Budget, BudgetStats, Crossover, Mutation, ParentSelection, RandomReset, Stopwatch, Truncation,
+    Uniform,
 };
+use rand::prelude::{SliceRandom, ThreadRng};
+use std::fmt::Debug;
 
 /// GA stands for Genetic Algorithm
 /// - A: Action
@@ -36,41 +34,45 @@ pub trait Ga<A: Debug + Default + Copy, const CHR_SIZE: usize>: Debug + Clone {
         chromosome
     }
 
-    fn mate(
+    /// Crossover then mutation
+    fn mate<S, C: Crossover<A>, M: Mutation<A>>(
         rng: &mut ThreadRng,
+        config: &GaConfig<S, C, M>,
         possible_genes: &[Vec<A>; CHR_SIZE],
         chromosome0: &[A; CHR_SIZE],
         chromosome1: &[A; CHR_SIZE],
     ) -> [A; CHR_SIZE] {
         let mut chromosome = [A::default(); CHR_SIZE];
-        for (i, (gene0, gene1)) in chromosome0.iter().zip(chromosome1).enumerate() {
-            // 126/256 ~ 0.492
-            // 4/256 ~ 0.016
-            chromosome[i] = match rng.gen::<u8>() {
-                0..=125 => *gene0,
-                126..=251 => *gene1,
-                252..=255 => *possible_genes[i].choose(rng).unwrap_or(&A::default()),
-            }
-        }
+        config
+            .crossover
+            .crossover(rng, chromosome0, chromosome1, &mut chromosome);
+        config.mutation.mutate(rng, possible_genes, &mut chromosome);
 
         chromosome
     }
 
-    fn ga(
+    /// `history` receives the stats of each generation, the initial population included
+    fn ga<S: ParentSelection, C: Crossover<A>, M: Mutation<A>>(
         &mut self,
         rng: &mut ThreadRng,
+        config: &GaConfig<S, C, M>,
         population: &mut Vec<(i64, [A; CHR_SIZE])>,
         possible_genes: &mut [Vec<A>; CHR_SIZE],
-        total_time: Duration,
-    ) -> (i64, [A; CHR_SIZE]) {
-        let now = Instant::now();
-        let mut max_time = Duration::default();
+        budget: &Budget,
+        history: &mut Vec<GenerationStats>,
+    ) -> (i64, [A; CHR_SIZE], BudgetStats)
+    where
+        A: PartialEq,
+    {
+        // One iteration is one generation, one node is one evaluation
+        let mut watch = budget.start();
+        history.clear();
 
         self.clear_and_fill(possible_genes);
 
         // Can't build chromosome without choice
         if possible_genes.iter().map(Vec::len).sum::<usize>() == 0 {
-            return (0, [A::default(); CHR_SIZE]);
+            return (0, [A::default(); CHR_SIZE], watch.stats());
         }
 
         population.reserve(Self::POP_SIZE);
@@ -78,34 +80,192 @@ pub trait Ga<A: Debug + Default + Copy, const CHR_SIZE: usize>: Debug + Clone {
             population.push((0, Self::gen(rng, possible_genes)))
         }
 
-        // Sort in reversed way, best must be first
-        population
-            .iter_mut()
-            .for_each(|(score, chr)| *score = self.eval(chr));
+        evolve(
+            rng,
+            &mut watch,
+            &config.selection,
+            config.elitism,
+            Self::POP_SIZE,
+            population,
+            history,
+            |rng, chromosome0, chromosome1| {
+                Self::mate(rng, config, possible_genes, chromosome0, chromosome1)
+            },
+            |chromosome| self.eval(chromosome),
+        );
+
+        let (score, chromosome) = *population[..Self::SEL_SIZE].choose(rng).unwrap();
+        (score, chromosome, watch.stats())
+    }
+}
+
+/// Generational loop shared by the genetic algorithms
+///
+/// The initial population is scored, then each generation keeps the `elitism` best
+/// chromosomes and fills up to `pop_size` with children. The population is sorted best first.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn evolve<A: PartialEq, C: AsRef<[A]>, S: ParentSelection>(
+    rng: &mut ThreadRng,
+    watch: &mut Stopwatch,
+    selection: &S,
+    elitism: usize,
+    pop_size: usize,
+    population: &mut Vec<(i64, C)>,
+    history: &mut Vec<GenerationStats>,
+    mut mate: impl FnMut(&mut ThreadRng, &C, &C) -> C,
+    eval: impl Fn(&C) -> i64,
+) {
+    // Sort in reversed way, best must be first
+    population
+        .iter_mut()
+        .for_each(|(score, chr)| *score = eval(chr));
+    population.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    watch.add_nodes(population.len());
+    history.push(GenerationStats::new(population));
+
+    let elitism = elitism.min(pop_size);
+    let mut offspring = Vec::with_capacity(pop_size);
+    while watch.next_iteration() {
+        // Parents are picked from the whole sorted population
+        offspring.clear();
+        for _ in elitism..pop_size {
+            let chromosome0 = &population[selection.select(rng, population)].1;
+            let chromosome1 = &population[selection.select(rng, population)].1;
+            let chromosome = mate(rng, chromosome0, chromosome1);
+            offspring.push((eval(&chromosome), chromosome));
+        }
+
+        // The elite is kept as is
+        population.truncate(elitism);
+        population.append(&mut offspring);
         population.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        watch.add_nodes(pop_size - elitism);
+        history.push(GenerationStats::new(population));
+    }
+}
 
-        while now.elapsed() + max_time < total_time {
-            let t = Instant::now();
+/// Operators of the genetic algorithms
+/// - selection: Pick the parents
+/// - crossover, mutation: Build the children
+/// - elitism: Number of best chromosomes kept in the next generation
+///
+/// The default keeps the best chromosome, picks the parents among the 10% best,
+/// uses a uniform crossover and redraws 1.6% of the genes.
+#[derive(Debug, Clone, Copy)]
+pub struct GaConfig<S = Truncation, C = Uniform, M = RandomReset> {
+    pub selection: S,
+    pub crossover: C,
+    pub mutation: M,
+    pub elitism: usize,
+}
 
-            // Keep the 10% best chromosomes
-            population.truncate(10 * Self::POP_SIZE / 100);
+impl<S: Default, C: Default, M: Default> Default for GaConfig<S, C, M> {
+    fn default() -> Self {
+        Self {
+            selection: S::default(),
+            crossover: C::default(),
+            mutation: M::default(),
+            elitism: 1,
+        }
+    }
+}
 
-            // Perform crossover & mutation
-            for _ in population.len()..Self::POP_SIZE {
-                let chromosome0 = population.choose(rng).unwrap().1;
-                let chromosome1 = population.choose(rng).unwrap().1;
-                population.push((0, Self::mate(rng, possible_genes, &chromosome0, &chromosome1)))
-            }
+impl<S, C, M> GaConfig<S, C, M> {
+    pub fn new(selection: S, crossover: C, mutation: M) -> Self {
+        Self {
+            selection,
+            crossover,
+            mutation,
+            elitism: 1,
+        }
+    }
 
-            // Order
-            population
-                .iter_mut()
-                .for_each(|(score, chr)| *score = self.eval(chr));
-            population.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    pub fn with_elitism(self, elitism: usize) -> Self {
+        Self { elitism, ..self }
+    }
+}
+
+/// - best: Score of the best chromosome
+/// - mean: Mean score of the population
+/// - diversity: Mean fraction of genes differing from the best chromosome, in [0, 1]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GenerationStats {
+    pub best: i64,
+    pub mean: f64,
+    pub diversity: f64,
+}
+
+impl GenerationStats {
+    /// The population is sorted best first
+    pub fn new<A: PartialEq>(population: &[(i64, impl AsRef<[A]>)]) -> Self {
+        let Some((best, chromosome)) = population.first() else {
+            return Self::default();
+        };
+        let best_genes = chromosome.as_ref();
+        let n = population.len() as f64;
+
+        let mean = population.iter().map(|(s, _)| *s as f64).sum::<f64>() / n;
+        let diversity = population
+            .iter()
+            .map(|(_, chr)| {
+                let genes = chr.as_ref();
+                let diff = genes.iter().zip(best_genes).filter(|(a, b)| a != b).count();
+                diff as f64 / genes.len().max(1) as f64
+            })
+            .sum::<f64>()
+            / n;
+
+        Self {
+            best: *best,
+            mean,
+            diversity,
+        }
+    }
+}
+
+#[test]
+fn one_max() {
+    use crate::{Tournament, TwoPoint};
+
+    /// Maximise the number of ones
+    #[derive(Debug, Clone)]
+    struct OneMax;
 
-            max_time = std::cmp::max(max_time, t.elapsed());
+    impl Ga<u8, 32> for OneMax {
+        const POP_SIZE: usize = 50;
+        const SEL_SIZE: usize = 1;
+
+        fn fill(&self, possible_genes: &mut [Vec<u8>; 32]) {
+            possible_genes
+                .iter_mut()
+                .for_each(|genes| genes.extend([0, 1]));
         }
 
-        *population[..Self::SEL_SIZE].choose(rng).unwrap()
+        fn eval(&self, chromosome: &[u8; 32]) -> i64 {
+            chromosome.iter().map(|&g| g as i64).sum()
+        }
     }
+
+    let mut rng = rand::thread_rng();
+    let mut possible_genes = std::array::from_fn(|_| Vec::new());
+    let mut history = Vec::new();
+    let config =
+        GaConfig::new(Tournament { size: 3 }, TwoPoint, RandomReset { rate: 0.02 }).with_elitism(2);
+
+    let (score, _, stats) = OneMax.ga(
+        &mut rng,
+        &config,
+        &mut Vec::new(),
+        &mut possible_genes,
+        &Budget::iterations(100),
+        &mut history,
+    );
+
+    assert_eq!(stats.iterations, 100);
+    assert_eq!(stats.nodes, 50 + 100 * 48);
+    assert_eq!(history.len(), 101);
+    assert!(score >= 30);
+    // Elitism: the best score never decreases
+    assert!(history.windows(2).all(|w| w[0].best <= w[1].best));
+    assert!(history[100].diversity < history[0].diversity);
 }
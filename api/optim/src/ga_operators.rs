@@ -0,0 +1,346 @@
+use rand::{
+    prelude::{SliceRandom, ThreadRng},
+    Rng,
+};
+
+/// Pick a parent, the population is sorted best first
+pub trait ParentSelection {
+    fn select<C>(&self, rng: &mut ThreadRng, population: &[(i64, C)]) -> usize;
+}
+
+/// Uniform among the best `rate` of the population
+#[derive(Debug, Clone, Copy)]
+pub struct Truncation {
+    pub rate: f64,
+}
+
+impl Default for Truncation {
+    fn default() -> Self {
+        Self { rate: 0.1 }
+    }
+}
+
+impl ParentSelection for Truncation {
+    fn select<C>(&self, rng: &mut ThreadRng, population: &[(i64, C)]) -> usize {
+        let n = (self.rate * population.len() as f64).ceil() as usize;
+        rng.gen_range(0..n.clamp(1, population.len()))
+    }
+}
+
+/// Best of `size` chromosomes drawn at random
+#[derive(Debug, Clone, Copy)]
+pub struct Tournament {
+    pub size: usize,
+}
+
+impl ParentSelection for Tournament {
+    fn select<C>(&self, rng: &mut ThreadRng, population: &[(i64, C)]) -> usize {
+        // Sorted population, the best has the lowest index
+        (0..self.size.max(1))
+            .map(|_| rng.gen_range(0..population.len()))
+            .min()
+            .unwrap()
+    }
+}
+
+/// Probability proportional to the score shifted above the worst one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Roulette;
+
+impl ParentSelection for Roulette {
+    fn select<C>(&self, rng: &mut ThreadRng, population: &[(i64, C)]) -> usize {
+        let worst = population.last().unwrap().0;
+        // 1 is added to keep a chance for the worst ones
+        let weight = |score: i64| (score - worst) as f64 + 1.;
+        let total = population.iter().map(|(s, _)| weight(*s)).sum::<f64>();
+
+        let mut x = rng.gen_range(0. ..total);
+        for (i, (s, _)) in population.iter().enumerate() {
+            x -= weight(*s);
+            if x < 0. {
+                return i;
+            }
+        }
+        population.len() - 1
+    }
+}
+
+/// Probability proportional to the rank, the best has weight n, the worst 1
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rank;
+
+impl ParentSelection for Rank {
+    fn select<C>(&self, rng: &mut ThreadRng, population: &[(i64, C)]) -> usize {
+        let n = population.len();
+        let mut x = rng.gen_range(0..n * (n + 1) / 2);
+        for i in 0..n {
+            if x < n - i {
+                return i;
+            }
+            x -= n - i;
+        }
+        n - 1
+    }
+}
+
+/// Build a child from two parents of the same length
+pub trait Crossover<A> {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut [A]);
+}
+
+/// Two random cut points, 0 <= a <= b <= n
+fn cuts(rng: &mut ThreadRng, n: usize) -> (usize, usize) {
+    let a = rng.gen_range(0..=n);
+    let b = rng.gen_range(0..=n);
+    (a.min(b), a.max(b))
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OnePoint;
+
+impl<A: Copy> Crossover<A> for OnePoint {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut [A]) {
+        let cut = rng.gen_range(0..=child.len());
+        child[..cut].copy_from_slice(&parent0[..cut]);
+        child[cut..].copy_from_slice(&parent1[cut..]);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TwoPoint;
+
+impl<A: Copy> Crossover<A> for TwoPoint {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut [A]) {
+        let (a, b) = cuts(rng, child.len());
+        child.copy_from_slice(parent0);
+        child[a..b].copy_from_slice(&parent1[a..b]);
+    }
+}
+
+/// Each gene comes from either parent with the same probability
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uniform;
+
+impl<A: Copy> Crossover<A> for Uniform {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut [A]) {
+        for (i, gene) in child.iter_mut().enumerate() {
+            *gene = if rng.gen() { parent0[i] } else { parent1[i] };
+        }
+    }
+}
+
+/// Order crossover (OX1) for permutations
+///
+/// A slice of the first parent is kept, the other genes follow the order of the second one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Order;
+
+impl<A: Copy + PartialEq> Crossover<A> for Order {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut [A]) {
+        let n = child.len();
+        let (a, b) = cuts(rng, n);
+        child[a..b].copy_from_slice(&parent0[a..b]);
+
+        let mut genes = (0..n)
+            .map(|i| parent1[(b + i) % n])
+            .filter(|gene| !parent0[a..b].contains(gene));
+        for i in (b..n).chain(0..a) {
+            child[i] = genes.next().unwrap();
+        }
+    }
+}
+
+/// Partially mapped crossover (PMX) for permutations
+///
+/// A slice of the first parent is kept, the displaced genes of the second one
+/// are placed following the mapping between both slices.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pmx;
+
+impl<A: Copy + PartialEq> Crossover<A> for Pmx {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut [A]) {
+        let (a, b) = cuts(rng, child.len());
+        child.copy_from_slice(parent1);
+        child[a..b].copy_from_slice(&parent0[a..b]);
+
+        for i in a..b {
+            let gene = parent1[i];
+            if parent0[a..b].contains(&gene) {
+                continue;
+            }
+            // Follow the mapping until a position out of the slice
+            let mut j = i;
+            while (a..b).contains(&j) {
+                j = parent1.iter().position(|g| *g == parent0[j]).unwrap();
+            }
+            child[j] = gene;
+        }
+    }
+}
+
+/// Modify a child in place
+pub trait Mutation<A> {
+    fn mutate(&self, rng: &mut ThreadRng, possible_genes: &[Vec<A>], chromosome: &mut [A]);
+}
+
+/// Each gene is redrawn from its possible alleles with probability `rate`
+#[derive(Debug, Clone, Copy)]
+pub struct RandomReset {
+    pub rate: f64,
+}
+
+impl Default for RandomReset {
+    fn default() -> Self {
+        Self { rate: 4. / 256. }
+    }
+}
+
+impl<A: Copy> Mutation<A> for RandomReset {
+    fn mutate(&self, rng: &mut ThreadRng, possible_genes: &[Vec<A>], chromosome: &mut [A]) {
+        for (gene, alleles) in chromosome.iter_mut().zip(possible_genes) {
+            if rng.gen_bool(self.rate) {
+                if let Some(allele) = alleles.choose(rng) {
+                    *gene = *allele;
+                }
+            }
+        }
+    }
+}
+
+/// Two random genes are swapped with probability `rate`, keeps permutations valid
+#[derive(Debug, Clone, Copy)]
+pub struct Swap {
+    pub rate: f64,
+}
+
+impl<A> Mutation<A> for Swap {
+    fn mutate(&self, rng: &mut ThreadRng, _: &[Vec<A>], chromosome: &mut [A]) {
+        if !chromosome.is_empty() && rng.gen_bool(self.rate) {
+            let i = rng.gen_range(0..chromosome.len());
+            let j = rng.gen_range(0..chromosome.len());
+            chromosome.swap(i, j);
+        }
+    }
+}
+
+/// A random slice is reversed with probability `rate`, keeps permutations valid
+#[derive(Debug, Clone, Copy)]
+pub struct Inversion {
+    pub rate: f64,
+}
+
+impl<A> Mutation<A> for Inversion {
+    fn mutate(&self, rng: &mut ThreadRng, _: &[Vec<A>], chromosome: &mut [A]) {
+        if rng.gen_bool(self.rate) {
+            let (a, b) = cuts(rng, chromosome.len());
+            chromosome[a..b].reverse();
+        }
+    }
+}
+
+/// Operator keeping permutations valid
+pub trait Permutation {}
+
+impl Permutation for Order {}
+impl Permutation for Pmx {}
+impl Permutation for Swap {}
+impl Permutation for Inversion {}
+
+/// Build a child from two parents of any length
+pub trait VarCrossover<A> {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut Vec<A>);
+}
+
+/// Same cut in both parents, the child has the length of the second one
+impl<A: Copy> VarCrossover<A> for OnePoint {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut Vec<A>) {
+        let cut = rng.gen_range(0..=parent0.len().min(parent1.len()));
+        child.clear();
+        child.extend_from_slice(&parent0[..cut]);
+        child.extend_from_slice(&parent1[cut..]);
+    }
+}
+
+/// A cut in each parent, the head of the first is joined to the tail of the second
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CutSplice;
+
+impl<A: Copy> VarCrossover<A> for CutSplice {
+    fn crossover(&self, rng: &mut ThreadRng, parent0: &[A], parent1: &[A], child: &mut Vec<A>) {
+        let cut0 = rng.gen_range(0..=parent0.len());
+        let cut1 = rng.gen_range(0..=parent1.len());
+        child.clear();
+        child.extend_from_slice(&parent0[..cut0]);
+        child.extend_from_slice(&parent1[cut1..]);
+    }
+}
+
+/// Modify a child of any length in place, all genes share the same alleles
+pub trait VarMutation<A> {
+    fn mutate(&self, rng: &mut ThreadRng, alleles: &[A], chromosome: &mut Vec<A>);
+}
+
+impl<A: Copy> VarMutation<A> for RandomReset {
+    fn mutate(&self, rng: &mut ThreadRng, alleles: &[A], chromosome: &mut Vec<A>) {
+        for gene in chromosome.iter_mut() {
+            if rng.gen_bool(self.rate) {
+                if let Some(allele) = alleles.choose(rng) {
+                    *gene = *allele;
+                }
+            }
+        }
+    }
+}
+
+/// Insert a random gene with probability `insert`, remove one with probability `delete`
+#[derive(Debug, Clone, Copy)]
+pub struct Resize {
+    pub insert: f64,
+    pub delete: f64,
+}
+
+impl<A: Copy> VarMutation<A> for Resize {
+    fn mutate(&self, rng: &mut ThreadRng, alleles: &[A], chromosome: &mut Vec<A>) {
+        if !chromosome.is_empty() && rng.gen_bool(self.delete) {
+            chromosome.remove(rng.gen_range(0..chromosome.len()));
+        }
+        if rng.gen_bool(self.insert) {
+            if let Some(allele) = alleles.choose(rng) {
+                chromosome.insert(rng.gen_range(0..=chromosome.len()), *allele);
+            }
+        }
+    }
+}
+
+/// Both mutations in sequence
+impl<A, M0: VarMutation<A>, M1: VarMutation<A>> VarMutation<A> for (M0, M1) {
+    fn mutate(&self, rng: &mut ThreadRng, alleles: &[A], chromosome: &mut Vec<A>) {
+        self.0.mutate(rng, alleles, chromosome);
+        self.1.mutate(rng, alleles, chromosome);
+    }
+}
+
+#[test]
+fn permutation() {
+    let mut rng = rand::thread_rng();
+    let mut parent0 = (0..20).collect::<Vec<_>>();
+    let mut parent1 = parent0.clone();
+    let mut child = vec![0; 20];
+
+    for _ in 0..100 {
+        parent0.shuffle(&mut rng);
+        parent1.shuffle(&mut rng);
+
+        Order.crossover(&mut rng, &parent0, &parent1, &mut child);
+        let mut sorted = child.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+
+        Pmx.crossover(&mut rng, &parent0, &parent1, &mut child);
+        Inversion { rate: 1. }.mutate(&mut rng, &[], &mut child);
+        Swap { rate: 1. }.mutate(&mut rng, &[], &mut child);
+        let mut sorted = child.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+}
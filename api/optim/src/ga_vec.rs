@@ -0,0 +1,267 @@
+use crate::{
+    evolve, Budget, BudgetStats, Crossover, GaConfig, GenerationStats, Mutation, ParentSelection,
+    Permutation, VarCrossover, VarMutation,
+};
+use rand::{
+    prelude::{SliceRandom, ThreadRng},
+    Rng,
+};
+use std::fmt::Debug;
+
+/// GA where a chromosome is an ordering of the genes given by `fill`,
+/// for routing or scheduling problems
+/// - POP_SIZE: Population size
+/// - SEL_SIZE: Population size kept (< POP_SIZE)
+///
+/// The operators must keep permutations valid: `Order` or `Pmx` with `Swap` or `Inversion`.
+pub trait PermutationGa<A: Debug + Copy + PartialEq>: Debug + Clone {
+    const POP_SIZE: usize;
+    const SEL_SIZE: usize;
+
+    /// Generate the genes to order
+    fn fill(&self, genes: &mut Vec<A>);
+
+    /// Heuristic evaluation
+    fn eval(&self, chromosome: &[A]) -> i64;
+
+    fn clear_and_fill(&self, genes: &mut Vec<A>) {
+        genes.clear();
+        self.fill(genes);
+    }
+
+    fn permutation_ga<S, C, M>(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &GaConfig<S, C, M>,
+        population: &mut Vec<(i64, Vec<A>)>,
+        genes: &mut Vec<A>,
+        budget: &Budget,
+        history: &mut Vec<GenerationStats>,
+    ) -> (i64, Vec<A>, BudgetStats)
+    where
+        S: ParentSelection,
+        C: Crossover<A> + Permutation,
+        M: Mutation<A> + Permutation,
+    {
+        // One iteration is one generation, one node is one evaluation
+        let mut watch = budget.start();
+        history.clear();
+
+        self.clear_and_fill(genes);
+        if genes.is_empty() {
+            return (0, Vec::new(), watch.stats());
+        }
+
+        population.reserve(Self::POP_SIZE);
+        for _ in 0..Self::POP_SIZE {
+            let mut chromosome = genes.clone();
+            chromosome.shuffle(rng);
+            population.push((0, chromosome))
+        }
+
+        evolve(
+            rng,
+            &mut watch,
+            &config.selection,
+            config.elitism,
+            Self::POP_SIZE,
+            population,
+            history,
+            |rng, chromosome0, chromosome1| {
+                let mut chromosome = chromosome0.clone();
+                config
+                    .crossover
+                    .crossover(rng, chromosome0, chromosome1, &mut chromosome);
+                config.mutation.mutate(rng, &[], &mut chromosome);
+                chromosome
+            },
+            |chromosome| self.eval(chromosome),
+        );
+
+        let (score, chromosome) = population[..Self::SEL_SIZE].choose(rng).unwrap().clone();
+        (score, chromosome, watch.stats())
+    }
+}
+
+/// GA with chromosomes of variable length
+/// - POP_SIZE: Population size
+/// - SEL_SIZE: Population size kept (< POP_SIZE)
+/// - MIN_LEN, MAX_LEN: Bounds of the chromosome length
+///
+/// Children out of the bounds are cut or completed with random genes.
+pub trait VarGa<A: Debug + Copy + PartialEq>: Debug + Clone {
+    const POP_SIZE: usize;
+    const SEL_SIZE: usize;
+    const MIN_LEN: usize;
+    const MAX_LEN: usize;
+
+    /// Generate all possible alleles, shared by every gene
+    fn fill(&self, possible_genes: &mut Vec<A>);
+
+    /// Heuristic evaluation
+    fn eval(&self, chromosome: &[A]) -> i64;
+
+    fn clear_and_fill(&self, possible_genes: &mut Vec<A>) {
+        possible_genes.clear();
+        self.fill(possible_genes);
+    }
+
+    /// Random length in [MIN_LEN, MAX_LEN]
+    fn gen(rng: &mut ThreadRng, possible_genes: &[A]) -> Vec<A> {
+        let len = rng.gen_range(Self::MIN_LEN..=Self::MAX_LEN.max(Self::MIN_LEN));
+        (0..len)
+            .filter_map(|_| possible_genes.choose(rng).copied())
+            .collect()
+    }
+
+    /// Crossover, mutation then length repair
+    fn mate<S, C: VarCrossover<A>, M: VarMutation<A>>(
+        rng: &mut ThreadRng,
+        config: &GaConfig<S, C, M>,
+        possible_genes: &[A],
+        chromosome0: &[A],
+        chromosome1: &[A],
+    ) -> Vec<A> {
+        let mut chromosome = Vec::with_capacity(Self::MAX_LEN);
+        config
+            .crossover
+            .crossover(rng, chromosome0, chromosome1, &mut chromosome);
+        config.mutation.mutate(rng, possible_genes, &mut chromosome);
+
+        chromosome.truncate(Self::MAX_LEN);
+        while chromosome.len() < Self::MIN_LEN {
+            chromosome.push(*possible_genes.choose(rng).unwrap());
+        }
+
+        chromosome
+    }
+
+    fn var_ga<S: ParentSelection, C: VarCrossover<A>, M: VarMutation<A>>(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &GaConfig<S, C, M>,
+        population: &mut Vec<(i64, Vec<A>)>,
+        possible_genes: &mut Vec<A>,
+        budget: &Budget,
+        history: &mut Vec<GenerationStats>,
+    ) -> (i64, Vec<A>, BudgetStats) {
+        // One iteration is one generation, one node is one evaluation
+        let mut watch = budget.start();
+        history.clear();
+
+        self.clear_and_fill(possible_genes);
+
+        // Can't build chromosome without choice
+        if possible_genes.is_empty() {
+            return (0, Vec::new(), watch.stats());
+        }
+
+        population.reserve(Self::POP_SIZE);
+        for _ in 0..Self::POP_SIZE {
+            population.push((0, Self::gen(rng, possible_genes)))
+        }
+
+        evolve(
+            rng,
+            &mut watch,
+            &config.selection,
+            config.elitism,
+            Self::POP_SIZE,
+            population,
+            history,
+            |rng, chromosome0, chromosome1| {
+                Self::mate(rng, config, possible_genes, chromosome0, chromosome1)
+            },
+            |chromosome| self.eval(chromosome),
+        );
+
+        let (score, chromosome) = population[..Self::SEL_SIZE].choose(rng).unwrap().clone();
+        (score, chromosome, watch.stats())
+    }
+}
+
+#[test]
+fn ga_vec() {
+    use crate::{CutSplice, Inversion, Order, RandomReset, Resize, Tournament};
+
+    /// Visit cities placed on a line, the shortest tour goes one way and comes back
+    #[derive(Debug, Clone)]
+    struct Tsp;
+
+    impl PermutationGa<i64> for Tsp {
+        const POP_SIZE: usize = 50;
+        const SEL_SIZE: usize = 1;
+
+        fn fill(&self, genes: &mut Vec<i64>) {
+            genes.extend(0..8);
+        }
+
+        fn eval(&self, chromosome: &[i64]) -> i64 {
+            let n = chromosome.len();
+            -(0..n)
+                .map(|i| (chromosome[i] - chromosome[(i + 1) % n]).abs())
+                .sum::<i64>()
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut history = Vec::new();
+    let budget = Budget::iterations(100);
+
+    let config = GaConfig::new(Tournament { size: 3 }, Order, Inversion { rate: 0.3 });
+    let (score, mut tour, _) = Tsp.permutation_ga(
+        &mut rng,
+        &config,
+        &mut Vec::new(),
+        &mut Vec::new(),
+        &budget,
+        &mut history,
+    );
+    // The optimum is -14, the GA only has to get close
+    assert!(score >= -16);
+    assert_eq!(score, Tsp.eval(&tour));
+    tour.sort();
+    assert_eq!(tour, (0..8).collect::<Vec<_>>());
+
+    /// Maximise the number of ones minus the number of zeros
+    #[derive(Debug, Clone)]
+    struct Ones;
+
+    impl VarGa<u8> for Ones {
+        const POP_SIZE: usize = 50;
+        const SEL_SIZE: usize = 1;
+        const MIN_LEN: usize = 2;
+        const MAX_LEN: usize = 10;
+
+        fn fill(&self, possible_genes: &mut Vec<u8>) {
+            possible_genes.extend([0, 1]);
+        }
+
+        fn eval(&self, chromosome: &[u8]) -> i64 {
+            chromosome.iter().map(|&g| 2 * g as i64 - 1).sum()
+        }
+    }
+
+    let mutation = (
+        RandomReset { rate: 0.05 },
+        Resize {
+            insert: 0.2,
+            delete: 0.2,
+        },
+    );
+    let config = GaConfig::new(Tournament { size: 3 }, CutSplice, mutation);
+    let mut population = Vec::new();
+    let (score, chromosome, _) = Ones.var_ga(
+        &mut rng,
+        &config,
+        &mut population,
+        &mut Vec::new(),
+        &budget,
+        &mut history,
+    );
+    assert!(score >= 8);
+    assert_eq!(score, Ones.eval(&chromosome));
+    assert!(population
+        .iter()
+        .all(|(_, chr)| (2..=10).contains(&chr.len())));
+}
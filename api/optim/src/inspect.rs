@@ -0,0 +1,240 @@
+use crate::Tree;
+use std::fmt::{Debug, Display, Write};
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// JSON has no infinity or NaN
+fn json_number(x: f64) -> String {
+    if x.is_finite() {
+        x.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Summary of a child in the tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChildStats<A> {
+    pub action: A,
+    pub node: usize,
+    pub visit: u64,
+    pub score: f64,
+    pub mean: f64,
+    pub prior: f64,
+}
+
+/// Depth-limited view of a tree keeping the `top_k` most visited children of each node
+pub struct TreeView<'a, A> {
+    tree: &'a Tree<A>,
+    max_depth: usize,
+    top_k: usize,
+}
+
+impl<A: Debug + Copy + PartialEq> Tree<A> {
+    /// Children sorted by visit, the most visited first
+    pub fn child_stats(&self, node: usize) -> Vec<ChildStats<A>> {
+        let mut stats = self
+            .children(node)
+            .map(|(action, child)| ChildStats {
+                action,
+                node: child,
+                visit: self[child].visit(),
+                score: self[child].score(),
+                mean: self[child].mean(),
+                prior: self[child].prior(),
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by_key(|child| std::cmp::Reverse(child.visit));
+        stats
+    }
+
+    /// Sequence of the most visited children from the root
+    pub fn principal_variation(&self) -> Vec<A> {
+        let mut pv = Vec::new();
+        let mut node = 0;
+        while let Some((action, child)) = self
+            .children(node)
+            .max_by_key(|(_, child)| self[*child].visit())
+        {
+            pv.push(action);
+            node = child;
+        }
+        pv
+    }
+
+    pub fn view(&self, max_depth: usize, top_k: usize) -> TreeView<'_, A> {
+        TreeView {
+            tree: self,
+            max_depth,
+            top_k,
+        }
+    }
+}
+
+impl<A: Debug + Copy + PartialEq> TreeView<'_, A> {
+    /// Nodes in preorder with their depth, the root has depth 0
+    pub fn walk(&self) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            out.push((node, depth));
+            if depth < self.max_depth {
+                let children = self.tree.child_stats(node);
+                children
+                    .iter()
+                    .take(self.top_k)
+                    .rev()
+                    .for_each(|child| stack.push((child.node, depth + 1)));
+            }
+        }
+        out
+    }
+
+    fn label(&self, node: usize) -> String {
+        self.tree
+            .action(node)
+            .map_or_else(|| "root".to_string(), |action| format!("{action:?}"))
+    }
+
+    /// Graphviz format
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph mcts {\n");
+        for (node, _) in self.walk() {
+            let n = &self.tree[node];
+            let label = self
+                .label(node)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            writeln!(
+                out,
+                "    n{node} [label=\"{label}\\n{}/{} - {:.2}\"];",
+                n.score(),
+                n.visit(),
+                n.mean()
+            )
+            .unwrap();
+            if let Some(parent) = self.tree.parent(node) {
+                writeln!(out, "    n{parent} -> n{node};").unwrap();
+            }
+        }
+        out.push('}');
+        out
+    }
+
+    /// Nested objects with the fields action, visit, score, mean, prior and children
+    pub fn to_json(&self) -> String {
+        let walk = self.walk();
+        let mut out = String::new();
+
+        for (i, &(node, depth)) in walk.iter().enumerate() {
+            let n = &self.tree[node];
+            let action = match self.tree.action(node) {
+                Some(action) => json_string(&format!("{action:?}")),
+                None => "null".to_string(),
+            };
+            write!(
+                out,
+                "{{\"action\":{action},\"visit\":{},\"score\":{},\"mean\":{},\"prior\":{},\"children\":[",
+                n.visit(),
+                json_number(n.score()),
+                json_number(n.mean()),
+                json_number(n.prior())
+            )
+            .unwrap();
+
+            // Close the node and its ancestors until the depth of the next node
+            let next_depth = walk.get(i + 1).map_or(0, |x| x.1);
+            if next_depth > depth {
+                continue;
+            }
+            out.push_str("]}");
+            (next_depth..depth).for_each(|_| out.push_str("]}"));
+            if i + 1 < walk.len() {
+                out.push(',');
+            }
+        }
+        out
+    }
+}
+
+impl<A: Debug + Copy + PartialEq> Display for TreeView<'_, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (node, depth) in self.walk() {
+            let n = &self.tree[node];
+            if depth == 0 {
+                writeln!(f, "{}/{}", n.score(), n.visit())?;
+                continue;
+            }
+            let space = (0..2 * (depth - 1)).map(|_| '-').collect::<String>();
+            writeln!(
+                f,
+                "{space}{}(score: {}/{} - {:.2})",
+                self.label(node),
+                n.score(),
+                n.visit(),
+                n.mean(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<A: Debug + Copy + PartialEq> Debug for Tree<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.view(usize::MAX, usize::MAX))
+    }
+}
+
+#[test]
+fn inspect() {
+    let mut tree = Tree::<char>::default();
+    let a = tree.add_child(0, 'a', 0.5).unwrap();
+    let b = tree.add_child(0, 'b', 0.5).unwrap();
+    let c = tree.add_child(b, 'c', 1.).unwrap();
+    for (node, visit, score) in [(0, 4, 2.), (a, 1, 0.), (b, 3, 2.), (c, 2, 2.)] {
+        tree[node].visit = visit;
+        tree[node].score = score;
+    }
+
+    assert_eq!(tree.principal_variation(), vec!['b', 'c']);
+    assert_eq!(tree.child_stats(0)[0].action, 'b');
+    assert_eq!(tree.eval(0), None);
+    assert_eq!(tree.view(1, 1).to_string(), "2/4\n'b'(score: 2/3 - 0.67)\n");
+    assert_eq!(
+        tree.view(usize::MAX, usize::MAX).to_json(),
+        "{\"action\":null,\"visit\":4,\"score\":2,\"mean\":0.5,\"prior\":1,\"children\":[\
+         {\"action\":\"'b'\",\"visit\":3,\"score\":2,\"mean\":0.6666666666666666,\"prior\":0.5,\"children\":[\
+         {\"action\":\"'c'\",\"visit\":2,\"score\":2,\"mean\":1,\"prior\":1,\"children\":[]}]},\
+         {\"action\":\"'a'\",\"visit\":1,\"score\":0,\"mean\":0,\"prior\":0.5,\"children\":[]}]}"
+    );
+    assert!(tree.view(1, 2).to_dot().contains("n0 -> n1;"));
+
+    let mut tree = Tree::<&str>::default();
+    tree.add_child(0, "\"", 1.).unwrap();
+    assert!(tree
+        .view(1, 1)
+        .to_dot()
+        .contains(r#"n1 [label="\"\\\"\"\n"#));
+
+    let mut tree = Tree::<char>::default();
+    tree.add_child(0, 'a', f64::INFINITY).unwrap();
+    tree[1].backpropagate(f64::NAN);
+    assert_eq!(
+        tree.view(1, 1).to_json(),
+        "{\"action\":null,\"visit\":0,\"score\":0,\"mean\":null,\"prior\":1,\"children\":[\
+         {\"action\":\"'a'\",\"visit\":0,\"score\":null,\"mean\":null,\"prior\":null,\"children\":[]}]}"
+    );
+}
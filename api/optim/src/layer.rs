@@ -0,0 +1,248 @@
+use crate::Backend;
+use rand::{
+    distributions::Uniform,
+    prelude::{Distribution, ThreadRng},
+};
+use rand_distr::Normal;
+use vector::VectorOp;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Activation {
+    #[default]
+    Linear,
+    Relu,
+    Tanh,
+    Sigmoid,
+    Softmax,
+}
+
+impl Activation {
+    pub fn apply(&self, x: &mut [f32]) {
+        match self {
+            Activation::Linear => (),
+            Activation::Relu => x.iter_mut().for_each(|x| *x = x.max(0.)),
+            Activation::Tanh => x.iter_mut().for_each(|x| *x = x.tanh()),
+            Activation::Sigmoid => x.iter_mut().for_each(|x| *x = 1. / (1. + (-*x).exp())),
+            Activation::Softmax => {
+                let max = x.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                x.iter_mut().for_each(|x| *x = (*x - max).exp());
+                let c = 1. / x.iter().sum::<f32>();
+                x.iter_mut().for_each(|x| *x *= c);
+            }
+        }
+    }
+
+    /// Turn the gradient of the output into the gradient of the input, from the output
+    pub fn backward(&self, output: &[f32], grad: &mut [f32]) {
+        let g = grad.iter_mut().zip(output);
+        match self {
+            Activation::Linear => (),
+            Activation::Relu => g.for_each(|(g, y)| *g = if *y > 0. { *g } else { 0. }),
+            Activation::Tanh => g.for_each(|(g, y)| *g *= 1. - y * y),
+            Activation::Sigmoid => g.for_each(|(g, y)| *g *= y * (1. - y)),
+            Activation::Softmax => {
+                let dot = VectorOp::dot(grad, output);
+                grad.iter_mut()
+                    .zip(output)
+                    .for_each(|(g, y)| *g = y * (*g - dot));
+            }
+        }
+    }
+}
+
+/// Fully connected layer: output = activation(weights * input + bias)
+/// - weights: outputs rows of inputs columns
+/// - normalize: The input is centred and scaled to a unit variance first
+/// - backend: Products of the forward and backward passes, the fastest one by default
+///
+/// The forward pass keeps what the backward pass needs, the gradients are accumulated
+/// until `zero_grad`.
+#[derive(Debug, Clone)]
+pub struct Dense {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub weights: Vec<f32>,
+    pub bias: Vec<f32>,
+    pub activation: Activation,
+    pub normalize: bool,
+    pub backend: Backend,
+    pub grad_weights: Vec<f32>,
+    pub grad_bias: Vec<f32>,
+    /// Input after normalisation
+    input: Vec<f32>,
+    output: Vec<f32>,
+    /// Standard deviation of the input and scale applied by the normalisation
+    sigma: f32,
+    scale: f32,
+}
+
+impl Dense {
+    /// He initialisation for ReLU, Xavier otherwise
+    pub fn new(rng: &mut ThreadRng, inputs: usize, outputs: usize, activation: Activation) -> Self {
+        let weights = if activation == Activation::Relu {
+            let d = Normal::new(0., (2. / inputs as f32).sqrt()).unwrap();
+            (0..inputs * outputs).map(|_| d.sample(rng)).collect()
+        } else {
+            let limit = (6. / (inputs + outputs) as f32).sqrt();
+            let d = Uniform::new_inclusive(-limit, limit);
+            (0..inputs * outputs).map(|_| d.sample(rng)).collect()
+        };
+        Self::from_parameters(inputs, weights, vec![0.; outputs], activation)
+    }
+
+    /// `weights` holds `bias.len()` rows of `inputs` columns
+    pub fn from_parameters(
+        inputs: usize,
+        weights: Vec<f32>,
+        bias: Vec<f32>,
+        activation: Activation,
+    ) -> Self {
+        let outputs = bias.len();
+        assert_eq!(weights.len(), inputs * outputs);
+
+        Self {
+            inputs,
+            outputs,
+            weights,
+            bias,
+            activation,
+            normalize: false,
+            backend: Backend::detect(),
+            grad_weights: vec![0.; inputs * outputs],
+            grad_bias: vec![0.; outputs],
+            input: vec![0.; inputs],
+            output: vec![0.; outputs],
+            sigma: 0.,
+            scale: 1.,
+        }
+    }
+
+    pub fn with_normalization(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn output(&self) -> &[f32] {
+        &self.output
+    }
+
+    pub fn forward(&mut self, input: &[f32]) -> &[f32] {
+        self.input.copy_from_slice(input);
+        if self.normalize {
+            let n = self.inputs as f32;
+            let mean = VectorOp::sum(&self.input) / n;
+            VectorOp::sub_assign_with(&mut self.input, mean);
+            self.sigma = (self.backend.dot(&self.input, &self.input) / n).sqrt();
+            self.scale = 1. / (self.sigma + 1e-8);
+            VectorOp::mul_assign_with(&mut self.input, self.scale);
+        }
+
+        for (i, out) in self.output.iter_mut().enumerate() {
+            let row = &self.weights[i * self.inputs..(i + 1) * self.inputs];
+            *out = self.backend.dot(row, &self.input) + self.bias[i];
+        }
+        self.activation.apply(&mut self.output);
+        &self.output
+    }
+
+    /// `grad` is the gradient of the output of the last forward pass, it is consumed
+    /// `grad_input` receives the gradient of its input
+    pub fn backward(&mut self, grad: &mut [f32], grad_input: &mut Vec<f32>) {
+        self.activation.backward(&self.output, grad);
+
+        for (i, g) in grad.iter().enumerate() {
+            let row = i * self.inputs..(i + 1) * self.inputs;
+            self.backend
+                .axpy(&mut self.grad_weights[row], *g, &self.input);
+            self.grad_bias[i] += g;
+        }
+
+        grad_input.clear();
+        grad_input.resize(self.inputs, 0.);
+        for (i, g) in grad.iter().enumerate() {
+            let row = &self.weights[i * self.inputs..(i + 1) * self.inputs];
+            self.backend.axpy(grad_input, *g, row);
+        }
+
+        if self.normalize {
+            // y = (x - mean) * scale, scale = 1 / (sigma + eps)
+            let n = self.inputs as f32;
+            let mean = VectorOp::sum(grad_input) / n;
+            let dot = VectorOp::dot(grad_input, &self.input);
+            let k = if self.sigma > 0. {
+                dot / (n * self.sigma)
+            } else {
+                0.
+            };
+            grad_input
+                .iter_mut()
+                .zip(&self.input)
+                .for_each(|(g, y)| *g = self.scale * (*g - mean) - k * y);
+        }
+    }
+
+    pub fn zero_grad(&mut self) {
+        self.grad_weights.iter_mut().for_each(|g| *g = 0.);
+        self.grad_bias.iter_mut().for_each(|g| *g = 0.);
+    }
+
+    /// (parameters, gradients) of the weights and the bias
+    pub fn parameters(&mut self) -> [(&mut [f32], &mut [f32]); 2] {
+        [
+            (&mut self.weights, &mut self.grad_weights),
+            (&mut self.bias, &mut self.grad_bias),
+        ]
+    }
+}
+
+#[test]
+fn dense() {
+    let mut rng = rand::thread_rng();
+    let input = [0.3, -1.2, 0.8, 2.];
+
+    // Finite differences on the sum of the outputs
+    for activation in [
+        Activation::Linear,
+        Activation::Relu,
+        Activation::Tanh,
+        Activation::Sigmoid,
+        Activation::Softmax,
+    ] {
+        let mut layer = Dense::new(&mut rng, 4, 3, activation).with_normalization();
+        let weights = [
+            0.5, -0.2, 0.1, 0.3, -0.4, 0.6, 0.2, -0.1, 0.3, 0.1, -0.5, 0.2,
+        ];
+        layer.weights.copy_from_slice(&weights);
+        let coef = [1., -2., 0.5];
+        let loss = |layer: &mut Dense, input: &[f32]| VectorOp::dot(layer.forward(input), &coef);
+
+        loss(&mut layer, &input);
+        let mut grad = coef.to_vec();
+        let mut grad_input = Vec::new();
+        layer.backward(&mut grad, &mut grad_input);
+
+        for j in 0..4 {
+            let mut x = input;
+            x[j] += 1e-2;
+            let up = loss(&mut layer, &x);
+            x[j] -= 2e-2;
+            let down = loss(&mut layer, &x);
+            assert!(
+                (grad_input[j] - (up - down) / 2e-2).abs() < 1e-2,
+                "{activation:?}"
+            );
+        }
+
+        let g = layer.grad_weights[5];
+        layer.weights[5] += 1e-2;
+        let up = loss(&mut layer, &input);
+        layer.weights[5] -= 2e-2;
+        let down = loss(&mut layer, &input);
+        assert!((g - (up - down) / 2e-2).abs() < 1e-2, "{activation:?}");
+    }
+}
@@ -1,13 +1,59 @@
+mod alpha_zero;
+mod arena;
+mod backend;
+mod budget;
+mod continuous;
+mod dqn;
+mod export;
 mod ga;
+mod ga_operators;
+mod ga_vec;
+mod inspect;
+mod layer;
+mod local_search;
 mod mcts;
 mod mcts_flow;
+mod mcts_game;
+mod model;
 mod monte_carlo;
+mod negamax;
 mod nn;
+mod nsga2;
+mod optimizer;
+mod parallel;
+mod rollout;
+mod search;
 mod simplex;
+mod train;
+mod tree;
+mod uct;
 
+pub use alpha_zero::*;
+pub use arena::*;
+pub use backend::*;
+pub use budget::*;
+pub use continuous::*;
+pub use dqn::*;
+pub use export::*;
 pub use ga::*;
+pub use ga_operators::*;
+pub use ga_vec::*;
+pub use inspect::*;
+pub use layer::*;
+pub use local_search::*;
 pub use mcts::*;
 pub use mcts_flow::*;
+pub use mcts_game::*;
+pub use model::*;
 pub use monte_carlo::*;
+pub use negamax::*;
 pub use nn::*;
+pub use nsga2::*;
+pub use optimizer::*;
+pub use parallel::*;
+pub use rollout::*;
+pub use search::*;
 pub use simplex::*;
+pub use train::*;
+pub use tree::*;
+pub use uct::*;
@@ -0,0 +1,253 @@
+use crate::{Budget, BudgetStats};
+use rand::{
+    prelude::{SliceRandom, ThreadRng},
+    Rng,
+};
+use std::{collections::VecDeque, fmt::Debug};
+
+/// Temperature of the simulated annealing
+/// - Geometric: From t0 to t1, geometric in the budget usage (time, iterations or nodes)
+/// - Linear: From t0 to t1, linear in the budget usage
+/// - Exponential: t0 * alpha^iteration, does not depend on the budget
+#[derive(Debug, Clone, Copy)]
+pub enum Cooling {
+    Geometric { t0: f64, t1: f64 },
+    Linear { t0: f64, t1: f64 },
+    Exponential { t0: f64, alpha: f64 },
+}
+
+impl Cooling {
+    /// `usage` in [0, 1]
+    pub fn temperature(&self, usage: f64, iteration: usize) -> f64 {
+        let usage = usage.clamp(0., 1.);
+        match *self {
+            Cooling::Geometric { t0, t1 } => t0 * (t1 / t0).powf(usage),
+            Cooling::Linear { t0, t1 } => t0 + (t1 - t0) * usage,
+            Cooling::Exponential { t0, alpha } => t0 * alpha.powi(iteration as i32),
+        }
+    }
+}
+
+/// Move picked by the hill climbing
+/// - Steepest: Best move among all neighbours
+/// - FirstImprovement: First improving move, neighbours are visited in random order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Climb {
+    #[default]
+    Steepest,
+    FirstImprovement,
+}
+
+/// Local search on a state modified by moves
+///
+/// The searches keep the best state found in `self`.
+/// One iteration is one move applied, one node is one move evaluated.
+pub trait Neighbourhood: Debug + Clone {
+    type Move: Debug + Copy + PartialEq;
+
+    /// Score to maximise
+    fn eval(&self) -> i64;
+
+    /// Generate all moves from the state
+    fn fill(&self, moves: &mut Vec<Self::Move>);
+
+    fn apply(&mut self, m: Self::Move);
+
+    fn clear_and_fill(&self, moves: &mut Vec<Self::Move>) {
+        moves.clear();
+        self.fill(moves);
+    }
+
+    /// Change of score of a move, override for an incremental evaluation
+    fn delta(&self, m: Self::Move) -> i64 {
+        let mut next = self.clone();
+        next.apply(m);
+        next.eval() - self.eval()
+    }
+
+    /// Random move, override to avoid generating all the moves
+    fn random_move(&self, rng: &mut ThreadRng, moves: &mut Vec<Self::Move>) -> Option<Self::Move> {
+        self.clear_and_fill(moves);
+        moves.choose(rng).copied()
+    }
+
+    /// Move cancelling `m`, forbidden by the tabu search once `m` is applied
+    fn reverse(&self, m: Self::Move) -> Self::Move {
+        m
+    }
+
+    /// Stop at a local optimum, `rng` is used by `Climb::FirstImprovement`
+    fn hill_climbing(
+        &mut self,
+        rng: &mut ThreadRng,
+        budget: &Budget,
+        climb: Climb,
+        moves: &mut Vec<Self::Move>,
+    ) -> (i64, BudgetStats) {
+        let mut watch = budget.start();
+        let mut score = self.eval();
+
+        while watch.next_iteration() {
+            self.clear_and_fill(moves);
+            let best = match climb {
+                Climb::Steepest => {
+                    watch.add_nodes(moves.len());
+                    moves
+                        .iter()
+                        .map(|&m| (m, self.delta(m)))
+                        .max_by_key(|(_, delta)| *delta)
+                }
+                Climb::FirstImprovement => {
+                    moves.shuffle(rng);
+                    let mut evaluated = 0;
+                    let first = moves.iter().find_map(|&m| {
+                        evaluated += 1;
+                        let delta = self.delta(m);
+                        (delta > 0).then_some((m, delta))
+                    });
+                    watch.add_nodes(evaluated);
+                    first
+                }
+            };
+
+            match best {
+                Some((m, delta)) if delta > 0 => {
+                    self.apply(m);
+                    score += delta;
+                }
+                // Local optimum
+                _ => return (score, watch.stats()),
+            }
+        }
+        (score, watch.stats())
+    }
+
+    /// Worse moves are accepted with probability exp(delta / temperature)
+    fn simulated_annealing(
+        &mut self,
+        rng: &mut ThreadRng,
+        budget: &Budget,
+        cooling: Cooling,
+        moves: &mut Vec<Self::Move>,
+    ) -> (i64, BudgetStats) {
+        let mut watch = budget.start();
+        let mut score = self.eval();
+        let mut best = (score, self.clone());
+
+        while watch.next_iteration() {
+            let Some(m) = self.random_move(rng, moves) else {
+                break;
+            };
+            let delta = self.delta(m);
+            watch.add_nodes(1);
+
+            let temperature = cooling.temperature(watch.usage(), watch.iterations() - 1);
+            if delta >= 0 || rng.gen::<f64>() < (delta as f64 / temperature).exp() {
+                self.apply(m);
+                score += delta;
+                if score > best.0 {
+                    best = (score, self.clone());
+                }
+            }
+        }
+
+        *self = best.1;
+        (best.0, watch.stats())
+    }
+
+    /// Apply the best move that is not tabu, the reverse of the last `tenure` moves are tabu
+    ///
+    /// Aspiration: a tabu move is allowed if it leads to a new best score.
+    fn tabu_search(
+        &mut self,
+        budget: &Budget,
+        tenure: usize,
+        moves: &mut Vec<Self::Move>,
+    ) -> (i64, BudgetStats) {
+        let mut watch = budget.start();
+        let mut score = self.eval();
+        let mut best = (score, self.clone());
+        let mut tabu = VecDeque::with_capacity(tenure + 1);
+
+        while watch.next_iteration() {
+            self.clear_and_fill(moves);
+            watch.add_nodes(moves.len());
+            let Some((m, delta)) = moves
+                .iter()
+                .map(|&m| (m, self.delta(m)))
+                .filter(|(m, delta)| !tabu.contains(m) || score + delta > best.0)
+                .max_by_key(|(_, delta)| *delta)
+            else {
+                break;
+            };
+
+            tabu.push_back(self.reverse(m));
+            if tabu.len() > tenure {
+                tabu.pop_front();
+            }
+            self.apply(m);
+            score += delta;
+            if score > best.0 {
+                best = (score, self.clone());
+            }
+        }
+
+        *self = best.1;
+        (best.0, watch.stats())
+    }
+}
+
+#[test]
+fn local_search() {
+    /// Order the numbers by swapping two of them, the score counts the ordered pairs
+    #[derive(Debug, Clone)]
+    struct Sort(Vec<u8>);
+
+    impl Neighbourhood for Sort {
+        type Move = (usize, usize);
+
+        fn eval(&self) -> i64 {
+            let v = &self.0;
+            (0..v.len())
+                .flat_map(|i| (i + 1..v.len()).map(move |j| (v[i] < v[j]) as i64))
+                .sum()
+        }
+
+        fn fill(&self, moves: &mut Vec<Self::Move>) {
+            let n = self.0.len();
+            moves.extend((0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))));
+        }
+
+        fn apply(&mut self, (i, j): Self::Move) {
+            self.0.swap(i, j);
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut moves = Vec::new();
+    let budget = Budget::iterations(1000);
+    let start = Sort(vec![3, 7, 1, 9, 0, 5, 2, 8, 6, 4]);
+    let sorted = (0..10).collect::<Vec<_>>();
+
+    // Any unsorted state has an improving swap
+    for climb in [Climb::Steepest, Climb::FirstImprovement] {
+        let mut state = start.clone();
+        let (score, stats) = state.hill_climbing(&mut rng, &budget, climb, &mut moves);
+        assert_eq!(score, 45);
+        assert_eq!(state.0, sorted);
+        assert_eq!(stats.stop, None);
+    }
+
+    let mut state = start.clone();
+    let cooling = Cooling::Geometric { t0: 2., t1: 0.01 };
+    let (score, stats) = state.simulated_annealing(&mut rng, &budget, cooling, &mut moves);
+    // Random moves, sorted in practice but only close to it for sure
+    assert!(score >= 40);
+    assert_eq!(state.eval(), score);
+    assert_eq!(stats.iterations, 1000);
+
+    let mut state = start;
+    let (score, _) = state.tabu_search(&Budget::iterations(50), 5, &mut moves);
+    assert_eq!(score, 45);
+    assert_eq!(state.eval(), 45);
+}
@@ -1,177 +1,158 @@
-use rand::prelude::{SliceRandom, ThreadRng};
-use std::{
-    cell::RefCell,
-    collections::HashMap,
-    fmt::Debug,
-    hash::Hash,
-    rc::{Rc, Weak},
-    time::{Duration, Instant},
+use crate::{
+    Budget, BudgetStats, DefaultPolicy, Expansion, RandomPolicy, Rollout, RolloutConfig, Score,
+    Selection, Tree, Ucb1,
 };
-
-/// - parent: Node
-/// - childen: HashMap<Action, Node>
-/// - visit: u64,
-/// - score: i64,
-pub struct Node<A> {
-    parent: Weak<Node<A>>,
-    children: RefCell<HashMap<A, Rc<Node<A>>>>,
-    visit: RefCell<u64>,
-    score: RefCell<i64>,
+use rand::prelude::ThreadRng;
+use std::{fmt::Debug, hash::Hash};
+
+/// - selection: Tree policy
+/// - expansion: When nodes are added to the tree
+/// - policy: Default policy used out of the tree
+/// - rollout: Depth cap and discount, the depth starts at the root
+#[derive(Debug, Clone, Default)]
+pub struct MctsConfig<S = Ucb1, P = RandomPolicy> {
+    pub selection: S,
+    pub expansion: Expansion,
+    pub policy: P,
+    pub rollout: RolloutConfig,
 }
 
-impl<A> Default for Node<A> {
-    fn default() -> Self {
+impl<S, P> MctsConfig<S, P> {
+    pub fn new(selection: S, policy: P) -> Self {
         Self {
-            parent: Default::default(),
-            children: Default::default(),
-            visit: Default::default(),
-            score: Default::default(),
+            selection,
+            expansion: Expansion::default(),
+            policy,
+            rollout: RolloutConfig::default(),
         }
     }
-}
-
-impl<A: Debug + Copy> Debug for Node<A> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fn formatter<A: Debug + Copy>(
-            node: &Node<A>,
-            f: &mut std::fmt::Formatter<'_>,
-            depth: usize,
-        ) -> std::fmt::Result {
-            for (action, child) in node.children.borrow().iter() {
-                let space = (0..depth).map(|_| '-').collect::<String>();
-                writeln!(
-                    f,
-                    "{space}{action:?}(score: {}/{} - {:.2})",
-                    child.score.borrow(),
-                    child.visit.borrow(),
-                    child.eval(),
-                )?;
-                formatter(child, f, depth + 2)?;
-            }
-            Ok(())
-        }
-        writeln!(f, "{}/{}", self.score.borrow(), self.visit.borrow())?;
-
-        formatter(self, f, 0)
-    }
-}
-
-impl<A: Copy> Node<A> {
-    pub fn eval(&self) -> f64 {
-        let score = *self.score.borrow() as f64;
-        let visit = *self.visit.borrow() as f64;
-        let pvisit = *self.parent.upgrade().unwrap().visit.borrow();
-        score / visit + (2. * (pvisit as f64).ln() / visit).sqrt()
-    }
-    pub fn best_action(&self) -> Option<A> {
-        self.children
-            .borrow()
-            .iter()
-            .max_by(|a, b| a.1.eval().partial_cmp(&b.1.eval()).unwrap())
-            .map(|x| *x.0)
-    }
 
-    pub fn worst_action(&self) -> Option<A> {
-        self.children
-            .borrow()
-            .iter()
-            .min_by(|a, b| a.1.eval().partial_cmp(&b.1.eval()).unwrap())
-            .map(|x| *x.0)
+    pub fn with_expansion(mut self, expansion: Expansion) -> Self {
+        self.expansion = expansion;
+        self
     }
-}
 
-pub struct Garbage<A>(Vec<Rc<Node<A>>>);
-
-impl<A> std::ops::Deref for Garbage<A> {
-    type Target = Vec<Rc<Node<A>>>;
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl<A> std::ops::DerefMut for Garbage<A> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    pub fn with_rollout(mut self, rollout: RolloutConfig) -> Self {
+        self.rollout = rollout;
+        self
     }
 }
 
 /// MCTS stands for Monte Carlo Tree Search
 /// - A: Action
-pub trait Mcts<A: Debug + Copy + Eq + Hash>: Debug + Clone {
-    /// Generate all possible actions
-    fn fill(&self, actions: &mut Vec<A>);
+///
+/// The value of a node is the discounted sum of the rewards from the action leading to it.
+pub trait Mcts<A: Debug + Copy + Eq + Hash>: Rollout<A> + Debug {
+    /// Prior probability of an action, uniform by default
+    fn prior(&self, _action: A, actions: &[A]) -> f64 {
+        1. / actions.len() as f64
+    }
+
+    /// Play the default policy from `depth` until it stops or the depth cap is reached
+    /// Return the score discounted from the state at `depth` and the number of updates
+    fn simulate<S, P: DefaultPolicy<Self, A>>(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &MctsConfig<S, P>,
+        depth: usize,
+        actions: &mut Vec<A>,
+    ) -> (f64, usize) {
+        let mut score = 0.;
+        let mut steps = 0;
+        self.clear_and_fill(actions);
 
-    /// Update the system
-    fn update(&mut self, action: A) -> i64;
+        while depth + steps < config.rollout.max_depth {
+            let Some(action) = config.policy.choose(rng, self, actions) else {
+                break;
+            };
+            score += config
+                .rollout
+                .discounted(self.update(action).to_f64(), steps);
+            steps += 1;
+            self.clear_and_fill(actions);
+        }
 
-    fn clear_and_fill(&self, actions: &mut Vec<A>) {
-        actions.clear();
-        self.fill(actions)
+        (score, steps)
     }
 
-    fn rollout(&mut self, rng: &mut ThreadRng, node: &Rc<Node<A>>, actions: &mut Vec<A>) {
-        self.clear_and_fill(actions);
-        *node.visit.borrow_mut() += 1;
-
-        if let Some(&action) = actions.choose(rng) {
-            // Update game with action
-            let score = self.update(action);
-
-            let mut node_children = node.children.borrow_mut();
-            if let Some(child) = node_children.get_mut(&action) {
-                // Update child node
-                let past_score = *child.score.borrow();
-                *child.score.borrow_mut() += score;
-
-                self.rollout(rng, child, actions);
-
-                // Update node (backpropagation)
-                *node.score.borrow_mut() += *child.score.borrow() - past_score;
-            } else {
-                // Create child node
-                let child = Rc::new(Node {
-                    parent: Rc::downgrade(node),
-                    children: Default::default(),
-                    visit: RefCell::new(0),
-                    score: RefCell::new(score),
-                });
-
-                self.rollout(rng, &child, actions);
-
-                // Update node (backpropagation)
-                *node.score.borrow_mut() += *child.score.borrow();
-                node_children.insert(action, child);
+    /// Return the score and the number of updates
+    fn rollout<S: Selection, P: DefaultPolicy<Self, A>>(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &MctsConfig<S, P>,
+        tree: &mut Tree<A>,
+        actions: &mut Vec<A>,
+    ) -> (f64, usize) {
+        let mut path = std::mem::take(&mut tree.path);
+        path.clear();
+        let mut node = 0;
+
+        // Selection & expansion
+        let (mut score, depth) = loop {
+            self.clear_and_fill(actions);
+            tree[node].visit += 1;
+            if path.len() >= config.rollout.max_depth {
+                break (0., 0);
             }
+
+            let Some((action, child, expanded)) = tree.descend(
+                rng,
+                &config.selection,
+                config.expansion,
+                node,
+                actions,
+                |action| self.prior(action, actions),
+            ) else {
+                break self.simulate(rng, config, path.len(), actions);
+            };
+
+            path.push((child, self.update(action).to_f64()));
+            node = child;
+
+            if expanded && config.expansion != Expansion::All {
+                tree[node].visit += 1;
+                break self.simulate(rng, config, path.len(), actions);
+            }
+        };
+
+        // Backpropagation
+        for &(node, reward) in path.iter().rev() {
+            score = reward + config.rollout.discount * score;
+            tree[node].backpropagate(score);
         }
+        let depth = depth + path.len();
+        tree.path = path;
+
+        (score, depth)
     }
 
-    fn mcts(
+    /// When the tree is full, it is pruned to half of its size
+    fn mcts<S: Selection, P: DefaultPolicy<Self, A>>(
         &mut self,
         rng: &mut ThreadRng,
-        total_time: Duration,
-        node: &Rc<Node<A>>,
+        budget: &Budget,
+        config: &MctsConfig<S, P>,
+        tree: &mut Tree<A>,
         actions: &mut Vec<A>,
-    ) {
-        let now = Instant::now();
-        let mut max_time = Duration::default();
-
-        // While there is time rollout on each child
-        while now.elapsed() + max_time < total_time {
-            let t = Instant::now();
-            self.clone().rollout(rng, node, actions);
-            max_time = std::cmp::max(max_time, t.elapsed())
+    ) -> BudgetStats {
+        let mut watch = budget.start();
+
+        // While there is budget rollout from the root
+        while watch.next_iteration() {
+            if tree.is_full() {
+                tree.prune(tree.max_nodes() / 2);
+            }
+            let (score, depth) = self.clone().rollout(rng, config, tree, actions);
+            tree[0].backpropagate(score);
+            watch.add_nodes(depth);
         }
+        watch.stats()
     }
 
-    fn advance(&mut self, garbage: &mut Garbage<A>, node: Rc<Node<A>>, action: A) -> Rc<Node<A>> {
-        self.update(action);
-        let child = node
-            .children
-            .borrow_mut()
-            .remove(&action)
-            .unwrap_or_default();
-        garbage.push(node);
-        child
+    /// Play the action and keep its subtree
+    fn advance(&mut self, tree: &mut Tree<A>, action: A) -> Self::Score {
+        tree.advance(action);
+        self.update(action)
     }
 }
 
@@ -194,7 +175,9 @@ fn test() {
         A3,
     }
 
-    impl Mcts<Action> for Game {
+    impl Rollout<Action> for Game {
+        type Score = i64;
+
         fn fill(&self, actions: &mut Vec<Action>) {
             match self {
                 Game::State0 => {
@@ -228,12 +211,27 @@ fn test() {
         }
     }
 
+    impl Mcts<Action> for Game {}
+
     let mut game = Game::State0;
     let mut rng = rand::thread_rng();
-    let node = Rc::new(Node::default());
+    let mut tree = Tree::default();
     let mut actions = Vec::new();
 
-    game.mcts(&mut rng, Duration::from_micros(5000), &node, &mut actions);
-
-    println!("{node:?}")
+    let config: MctsConfig = MctsConfig::default();
+    let stats = game.mcts(
+        &mut rng,
+        &Budget::iterations(100),
+        &config,
+        &mut tree,
+        &mut actions,
+    );
+
+    assert_eq!(stats.iterations, 100);
+    assert_eq!(tree.root().visit(), 100);
+    assert_eq!(tree.best_action(), Some(Action::A1));
+    println!("{tree:?}");
+
+    game.advance(&mut tree, Action::A1);
+    assert_eq!(tree.best_action(), Some(Action::A3))
 }
@@ -1,92 +1,90 @@
-use rand::{prelude::ThreadRng, seq::SliceRandom};
-use std::{
-    fmt::Debug,
-    time::{Duration, Instant},
-};
-
-pub trait MctsFlow<A: Debug + Copy>: Clone {
-    /// Generate all possible actions
-    fn fill(&self, actions: &mut Vec<A>);
-    /// Update the system
-    fn update(&mut self, action: A) -> i32;
-
-    fn clear_and_fill(&self, actions: &mut Vec<A>) {
-        actions.clear();
-        self.fill(actions)
-    }
+use crate::{Budget, BudgetStats, Rollout, RolloutConfig, Score};
+use rand::prelude::ThreadRng;
+use std::{fmt::Debug, time::Instant};
 
+pub trait MctsFlow<A: Debug + Copy>: Rollout<A> {
+    /// Return the score and the number of updates
     fn run(
         &self,
         rng: &mut ThreadRng,
+        config: &RolloutConfig,
         action: A,
         actions: &mut Vec<A>,
-    ) -> i32 {
+    ) -> (Self::Score, usize) {
         let mut run = self.clone();
-        let mut score = run.update(action);
-        run.clear_and_fill(actions);
-
-        while let Some(action) = actions.choose(rng) {
-            score += run.update(*action);
-            run.clear_and_fill(actions);
-        }
-
-        score
+        let score = run.update(action).to_f64();
+        let (rest, depth) = run.random_rollout(rng, config, 1, actions);
+        (Self::Score::from_f64(score + rest), depth + 1)
     }
 
+    /// Return the total score, the number of runs is in the stats
     fn multi_run(
         &self,
         rng: &mut ThreadRng,
-        max: Duration,
+        budget: &Budget,
+        config: &RolloutConfig,
         action: A,
         actions: &mut Vec<A>,
-    ) -> (i32, usize) {
-        let now = Instant::now();
-        let mut max_time = Duration::default();
-        let mut s = 0;
-        let mut n = 0;
+    ) -> (Self::Score, BudgetStats) {
+        let mut watch = budget.start();
+        let mut s = Self::Score::default();
 
-        while now.elapsed() + max_time < max {
-            let t = Instant::now();
-            s += self.run(rng, action, actions);
-            n += 1;
-            max_time = std::cmp::max(max_time, t.elapsed())
+        while watch.next_iteration() {
+            let (score, depth) = self.run(rng, config, action, actions);
+            watch.add_nodes(depth);
+            s += score;
         }
 
-        (s, n)
+        (s, watch.stats())
     }
 
+    /// The budget is shared evenly between the possible actions
     fn mcts(
         &self,
         rng: &mut ThreadRng,
-        max_time: Duration,
+        budget: &Budget,
+        config: &RolloutConfig,
         actions: &mut Vec<A>,
         buffer: &mut Vec<A>,
-        scores: &mut Vec<(i32, usize)>,
-    ) -> Option<(A, f32)> {
+        scores: &mut Vec<(Self::Score, usize)>,
+    ) -> Option<(A, f64, BudgetStats)> {
         self.clear_and_fill(actions);
         if actions.is_empty() {
             return None;
         }
-        let max_time = Duration::from_nanos((max_time.as_nanos() / actions.len() as u128) as u64);
+        let now = Instant::now();
+        let budget = budget.split(actions.len());
+        let mut stats = BudgetStats::default();
 
         scores.clear();
 
-        actions
-            .iter()
-            .copied()
-            .for_each(|a| scores.push(self.multi_run(rng, max_time, a, buffer)));
+        actions.iter().copied().for_each(|a| {
+            let (s, run_stats) = self.multi_run(rng, &budget, config, a, buffer);
+            scores.push((s, run_stats.iterations));
+            stats += run_stats;
+        });
+        stats.elapsed = now.elapsed();
+
+        Self::best(actions, scores).map(|(a, score)| (a, score, stats))
+    }
 
-        let n_tot = scores.iter().map(|(_, n)| n).sum::<usize>() as f32;
+    /// Action with the best UCB1 from the total score and the number of runs,
+    /// the actions never run come last
+    fn best(actions: &[A], scores: &[(Self::Score, usize)]) -> Option<(A, f64)> {
+        let n_tot = scores.iter().map(|(_, n)| n).sum::<usize>() as f64;
 
         actions
             .iter()
             .copied()
             .zip(scores.iter().copied())
             .map(|(a, (s, n))| {
-                let n = n as f32;
-                let s = s as f32;
+                if n == 0 {
+                    return (a, f64::NEG_INFINITY);
+                }
+                let n = n as f64;
+                let s = s.to_f64();
                 (a, s / n + (2. * n_tot.ln() / n).sqrt())
             })
-            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .max_by(|a, b| a.1.total_cmp(&b.1))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,234 @@
+use crate::{
+    Budget, BudgetStats, DefaultPolicy, Expansion, Game, MctsConfig, Selection, Status, Tree,
+};
+use rand::prelude::ThreadRng;
+
+/// Reward of a player for a finished game
+/// - Win: 1 for the winner, 0 for the others
+/// - Draw: 0.5 for everyone, a game ended without status is a draw
+pub fn outcome(status: Status, player: usize) -> f64 {
+    match status {
+        Status::Win(winner) if winner == player => 1.,
+        Status::Win(_) => 0.,
+        Status::Draw | Status::None => 0.5,
+    }
+}
+
+/// MCTS for games where players take turns
+///
+/// Each node holds the rewards of the player who played the action leading to it,
+/// so every player picks the best move for itself.
+pub trait GameMcts: Game {
+    fn player(&self) -> usize {
+        self.turn() % Self::PLAYERS
+    }
+
+    /// Play the default policy until the end of the game
+    /// Return the final status and the number of updates
+    fn playout<P: DefaultPolicy<Self, usize>>(
+        &mut self,
+        rng: &mut ThreadRng,
+        policy: &P,
+        actions: &mut Vec<usize>,
+    ) -> (Status, usize) {
+        let mut depth = 0;
+        self.fill(actions);
+
+        while let Some(action) = policy.choose(rng, self, actions) {
+            self.update(action);
+            depth += 1;
+            self.fill(actions);
+        }
+
+        (self.status(), depth)
+    }
+
+    /// Return the final status and the number of updates, `self` is left at the end of the game
+    fn game_rollout<S: Selection, P: DefaultPolicy<Self, usize>>(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &MctsConfig<S, P>,
+        tree: &mut Tree<usize>,
+        actions: &mut Vec<usize>,
+    ) -> (Status, usize) {
+        let mut path = std::mem::take(&mut tree.moves);
+        path.clear();
+        let mut node = 0;
+
+        // Selection & expansion
+        let (status, depth) = loop {
+            self.fill(actions);
+            tree[node].visit += 1;
+
+            let Some((action, child, expanded)) = tree.descend(
+                rng,
+                &config.selection,
+                config.expansion,
+                node,
+                actions,
+                |_| 1. / actions.len() as f64,
+            ) else {
+                break self.playout(rng, &config.policy, actions);
+            };
+
+            // Keep the player who moved
+            path.push((child, self.player()));
+            self.update(action);
+            node = child;
+
+            if expanded && config.expansion != Expansion::All {
+                tree[node].visit += 1;
+                break self.playout(rng, &config.policy, actions);
+            }
+        };
+
+        // Backpropagation, from the point of view of the player who moved
+        for &(node, player) in path.iter() {
+            tree[node].backpropagate(self.reward(player) as f64);
+        }
+        let depth = depth + path.len();
+        tree.moves = path;
+
+        (status, depth)
+    }
+
+    /// The root holds the rewards of the player to move
+    ///
+    /// When the tree is full, it is pruned to half of its size
+    fn game_mcts<S: Selection, P: DefaultPolicy<Self, usize>>(
+        &self,
+        rng: &mut ThreadRng,
+        budget: &Budget,
+        config: &MctsConfig<S, P>,
+        tree: &mut Tree<usize>,
+        actions: &mut Vec<usize>,
+    ) -> BudgetStats {
+        let mut watch = budget.start();
+        let player = self.player();
+
+        while watch.next_iteration() {
+            if tree.is_full() {
+                tree.prune(tree.max_nodes() / 2);
+            }
+            let mut game = self.clone();
+            let (_, depth) = game.game_rollout(rng, config, tree, actions);
+            tree[0].backpropagate(game.reward(player) as f64);
+            watch.add_nodes(depth);
+        }
+        watch.stats()
+    }
+
+    /// Play the action and keep its subtree
+    fn game_advance(&mut self, tree: &mut Tree<usize>, action: usize) {
+        tree.advance(action);
+        self.update(action)
+    }
+}
+
+impl<G: Game> GameMcts for G {}
+
+#[test]
+fn nim() {
+    /// Take 1 to 3 stones, the player taking the last one wins
+    #[derive(Debug, Clone)]
+    struct Nim {
+        stones: usize,
+        turn: usize,
+    }
+
+    impl Game for Nim {
+        fn input<const N: usize>(&self) -> [f32; N] {
+            [self.stones as f32; N]
+        }
+        fn turn(&self) -> usize {
+            self.turn
+        }
+        fn status(&self) -> Status {
+            if self.stones == 0 {
+                Status::Win((self.turn + 1) % 2)
+            } else {
+                Status::None
+            }
+        }
+        fn reward(&self, player: usize) -> f32 {
+            outcome(self.status(), player) as f32
+        }
+        fn fill(&self, actions: &mut Vec<usize>) {
+            actions.clear();
+            actions.extend((1..=3).filter(|&n| n <= self.stones));
+        }
+        fn update(&mut self, action: usize) {
+            self.stones -= action;
+            self.turn += 1;
+        }
+    }
+
+    let game = Nim { stones: 5, turn: 0 };
+    let mut rng = rand::thread_rng();
+    let mut tree = Tree::with_max_nodes(500);
+    let mut actions = Vec::new();
+    let config: MctsConfig = MctsConfig::default();
+
+    game.game_mcts(
+        &mut rng,
+        &Budget::iterations(2000),
+        &config,
+        &mut tree,
+        &mut actions,
+    );
+
+    // Leave 4 stones, the opponent can't win
+    assert_eq!(tree.best_action(), Some(1));
+    assert!(tree.len() <= 500);
+}
+
+#[test]
+fn graded() {
+    /// Pick a number once, the first player gets a share of the reward
+    #[derive(Debug, Clone)]
+    struct Pick(Option<usize>);
+
+    impl Game for Pick {
+        fn input<const N: usize>(&self) -> [f32; N] {
+            [self.0.unwrap_or(0) as f32; N]
+        }
+        fn turn(&self) -> usize {
+            self.0.is_some() as usize
+        }
+        fn status(&self) -> Status {
+            Status::None
+        }
+        fn reward(&self, player: usize) -> f32 {
+            let share = self.0.unwrap_or(0) as f32 / 3.;
+            if player == 0 {
+                share
+            } else {
+                1. - share
+            }
+        }
+        fn fill(&self, actions: &mut Vec<usize>) {
+            actions.clear();
+            if self.0.is_none() {
+                actions.extend(1..=3);
+            }
+        }
+        fn update(&mut self, action: usize) {
+            self.0 = Some(action);
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut tree = Tree::default();
+    let config: MctsConfig = MctsConfig::default();
+
+    Pick(None).game_mcts(
+        &mut rng,
+        &Budget::iterations(100),
+        &config,
+        &mut tree,
+        &mut Vec::new(),
+    );
+
+    // Neither a win nor a draw, only the rewards tell the actions apart
+    assert_eq!(tree.best_action(), Some(3));
+}
@@ -0,0 +1,271 @@
+use crate::{Activation, Dense, NN};
+use std::error::Error;
+
+/// Magic bytes at the start of a model file
+pub const MODEL_MAGIC: [u8; 4] = *b"OPNN";
+/// Version written by `NN::to_bytes`, older versions are still read
+pub const MODEL_VERSION: u32 = 1;
+
+/// Failure of `NN::from_bytes` and `NN::load`
+#[derive(Debug)]
+pub enum ModelError {
+    Io(std::io::Error),
+    /// Not a model file
+    Magic,
+    /// Unknown version, written by a newer one
+    Version(u32),
+    /// The file ends too early
+    Truncated,
+    /// Bytes left after the last layer
+    Trailing,
+    UnknownActivation(u8),
+    /// Size in the file against the size expected
+    Shape {
+        what: &'static str,
+        found: usize,
+        expected: usize,
+    },
+}
+
+impl core::fmt::Display for ModelError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ModelError::Io(e) => write!(f, "Error : {e}"),
+            ModelError::Magic => write!(f, "Error : Not a model file"),
+            ModelError::Version(v) => write!(f, "Error : Unsupported version {v}"),
+            ModelError::Truncated => write!(f, "Error : Truncated file"),
+            ModelError::Trailing => write!(f, "Error : Trailing bytes"),
+            ModelError::UnknownActivation(a) => write!(f, "Error : Unknown activation {a}"),
+            ModelError::Shape {
+                what,
+                found,
+                expected,
+            } => write!(f, "Error : {what} is {found}, expected {expected}"),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+impl From<std::io::Error> for ModelError {
+    fn from(e: std::io::Error) -> Self {
+        ModelError::Io(e)
+    }
+}
+
+fn activation_id(activation: Activation) -> u8 {
+    match activation {
+        Activation::Linear => 0,
+        Activation::Relu => 1,
+        Activation::Tanh => 2,
+        Activation::Sigmoid => 3,
+        Activation::Softmax => 4,
+    }
+}
+
+fn activation_from_id(id: u8) -> Result<Activation, ModelError> {
+    Ok(match id {
+        0 => Activation::Linear,
+        1 => Activation::Relu,
+        2 => Activation::Tanh,
+        3 => Activation::Sigmoid,
+        4 => Activation::Softmax,
+        _ => return Err(ModelError::UnknownActivation(id)),
+    })
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], ModelError> {
+        if self.0.len() < n {
+            return Err(ModelError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, ModelError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, ModelError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32s(&mut self, n: usize) -> Result<Vec<f32>, ModelError> {
+        let bytes = self.take(n.checked_mul(4).ok_or(ModelError::Truncated)?)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect())
+    }
+}
+
+fn check(what: &'static str, found: usize, expected: usize) -> Result<(), ModelError> {
+    if found == expected {
+        Ok(())
+    } else {
+        Err(ModelError::Shape {
+            what,
+            found,
+            expected,
+        })
+    }
+}
+
+/// Little endian binary format, the weights keep their exact bits
+/// - Header: magic, version, IN, OUT and the number of layers as u32
+/// - Each layer: inputs and outputs as u32, activation and normalisation as u8, then the
+///   weights by rows and the bias as f32
+///
+/// The optimizer state is not saved.
+impl<const IN: usize, const OUT: usize> NN<IN, OUT> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MODEL_MAGIC);
+        for x in [
+            MODEL_VERSION,
+            IN as u32,
+            OUT as u32,
+            self.layers.len() as u32,
+        ] {
+            out.extend_from_slice(&x.to_le_bytes());
+        }
+
+        for layer in self.layers.iter() {
+            out.extend_from_slice(&(layer.inputs as u32).to_le_bytes());
+            out.extend_from_slice(&(layer.outputs as u32).to_le_bytes());
+            out.push(activation_id(layer.activation));
+            out.push(layer.normalize as u8);
+            for x in layer.weights.iter().chain(layer.bias.iter()) {
+                out.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    /// The architecture comes from the header, it must go from IN to OUT
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModelError> {
+        let mut reader = Reader(bytes);
+        if reader.take(4).map_err(|_| ModelError::Magic)? != MODEL_MAGIC {
+            return Err(ModelError::Magic);
+        }
+        let version = reader.u32()?;
+        if version == 0 || version > MODEL_VERSION {
+            return Err(ModelError::Version(version));
+        }
+        check("IN", reader.u32()? as usize, IN)?;
+        check("OUT", reader.u32()? as usize, OUT)?;
+        let len = reader.u32()? as usize;
+        if len == 0 {
+            return Err(ModelError::Shape {
+                what: "Layer count",
+                found: 0,
+                expected: 1,
+            });
+        }
+
+        let mut layers = Vec::with_capacity(len.min(1024));
+        let mut inputs = IN;
+        for _ in 0..len {
+            check("Layer inputs", reader.u32()? as usize, inputs)?;
+            let outputs = reader.u32()? as usize;
+            let activation = activation_from_id(reader.u8()?)?;
+            let normalize = reader.u8()? != 0;
+            let weights = reader.f32s(inputs * outputs)?;
+            let bias = reader.f32s(outputs)?;
+
+            let mut layer = Dense::from_parameters(inputs, weights, bias, activation);
+            layer.normalize = normalize;
+            layers.push(layer);
+            inputs = outputs;
+        }
+        check("Last layer outputs", inputs, OUT)?;
+        if !reader.0.is_empty() {
+            return Err(ModelError::Trailing);
+        }
+
+        Ok(Self::from_layers(layers))
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    pub fn load(path: &str) -> Result<Self, ModelError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// Network with every kind of layer, trained once so that its weights don't fit a short decimal
+#[cfg(test)]
+pub(crate) fn sample_nn() -> NN<5, 3> {
+    let mut nn = NN::<5, 3>::builder()
+        .normalize()
+        .dense(8, Activation::Relu)
+        .dense(6, Activation::Tanh)
+        .normalize()
+        .dense(4, Activation::Sigmoid)
+        .output(Activation::Softmax);
+    nn.backward([0.1, 0.7, -0.3, 0.9, 0.2], [0.2, 0.3, 0.5]);
+    nn
+}
+
+#[test]
+fn model() {
+    let mut nn = sample_nn();
+
+    let path = std::env::temp_dir().join(format!("optim-model-{}.bin", std::process::id()));
+    let path = path.to_str().unwrap();
+    nn.save(path).unwrap();
+    let mut loaded = NN::<5, 3>::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    for (a, b) in nn.layers.iter().zip(&loaded.layers) {
+        assert_eq!(
+            (a.inputs, a.outputs, a.activation, a.normalize),
+            (b.inputs, b.outputs, b.activation, b.normalize)
+        );
+        let bits = |x: &[f32]| x.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&a.weights), bits(&b.weights));
+        assert_eq!(bits(&a.bias), bits(&b.bias));
+    }
+    let input = [0.5, -0.2, 0.3, 0.8, -1.];
+    assert_eq!(nn.predict(input), loaded.predict(input));
+
+    let bytes = nn.to_bytes();
+    assert!(matches!(
+        NN::<5, 3>::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(ModelError::Truncated)
+    ));
+    assert!(matches!(
+        NN::<4, 3>::from_bytes(&bytes),
+        Err(ModelError::Shape {
+            what: "IN",
+            found: 5,
+            expected: 4
+        })
+    ));
+    assert!(matches!(
+        NN::<5, 3>::from_bytes(b"{}"),
+        Err(ModelError::Magic)
+    ));
+    let mut newer = bytes.clone();
+    newer[4] = 2;
+    assert!(matches!(
+        NN::<5, 3>::from_bytes(&newer),
+        Err(ModelError::Version(2))
+    ));
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        NN::<5, 3>::from_bytes(&trailing),
+        Err(ModelError::Trailing)
+    ));
+    assert!(matches!(
+        NN::<5, 3>::load("/nonexistent/model.bin"),
+        Err(ModelError::Io(_))
+    ));
+}
@@ -1,75 +1,77 @@
-use rand::prelude::{SliceRandom, ThreadRng};
-use std::time::{Duration, Instant};
+use crate::{Budget, BudgetStats, Rollout, RolloutConfig, Score};
+use rand::prelude::ThreadRng;
+use std::time::Instant;
 
 /// A: Action
 ///
 /// The more depth, the less precision
-pub trait MonteCarlo<A: Copy + std::fmt::Debug>: Clone {
-    const RATE: f32;
-
-    /// Generate all possible actions, don't forget to clear
-    fn fill(&self, actions: &mut Vec<A>);
-    /// Update the system
-    fn update(&mut self, action: A) -> f32;
-
-    fn run(&self, action: A, actions: &mut Vec<A>, max_depth: usize, rng: &mut ThreadRng) -> f32 {
+pub trait MonteCarlo<A: Copy + std::fmt::Debug>: Rollout<A> {
+    /// Return the discounted score and the depth reached
+    fn run(
+        &self,
+        config: &RolloutConfig,
+        action: A,
+        actions: &mut Vec<A>,
+        rng: &mut ThreadRng,
+    ) -> (Self::Score, usize) {
         let mut run = self.clone();
-        let mut score = run.update(action);
-        let mut depth = 1;
-        run.fill(actions);
-
-        while let Some(action) = actions.choose(rng) {
-            if depth == max_depth {
-                return score;
-            }
-            score += Self::RATE.powi(depth as i32) * run.update(*action);
-            depth += 1;
-            run.fill(actions);
-        }
-
-        score
+        let score = run.update(action).to_f64();
+        let (rest, depth) = run.random_rollout(rng, config, 1, actions);
+        (Self::Score::from_f64(score + rest), depth + 1)
     }
 
+    /// Return the mean score, -inf without any run
     fn multi_run(
         &self,
-        max: Duration,
+        budget: &Budget,
+        config: &RolloutConfig,
         action: A,
         actions: &mut Vec<A>,
-        max_depth: usize,
         rng: &mut ThreadRng,
-    ) -> f32 {
-        let now = Instant::now();
-        let mut max_time = Duration::default();
+    ) -> (f64, BudgetStats) {
+        let mut watch = budget.start();
         let mut s = 0.;
         let mut n = 0.;
 
-        while now.elapsed() + max_time < max {
-            let t = Instant::now();
-            s += self.run(action, actions, max_depth, rng);
+        while watch.next_iteration() {
+            let (score, depth) = self.run(config, action, actions, rng);
+            watch.add_nodes(depth);
+            s += score.to_f64();
             n += 1.;
-            max_time = std::cmp::max(max_time, t.elapsed())
         }
 
-        s / n
+        if n == 0. {
+            return (f64::NEG_INFINITY, watch.stats());
+        }
+        (s / n, watch.stats())
     }
 
+    /// The budget is shared evenly between the possible actions
     fn mc_play(
         &self,
-        max: Duration,
+        budget: &Budget,
+        config: &RolloutConfig,
         actions: &mut Vec<A>,
         buffer: &mut Vec<A>,
-        max_depth: usize,
         rng: &mut ThreadRng,
-    ) -> Option<(A, f32)> {
-        self.fill(actions);
+    ) -> Option<(A, f64, BudgetStats)> {
+        self.clear_and_fill(actions);
         if actions.is_empty() {
             return None;
         }
-        let max = Duration::from_nanos((max.as_nanos() / actions.len() as u128) as u64);
-        actions
+        let now = Instant::now();
+        let budget = budget.split(actions.len());
+        let mut stats = BudgetStats::default();
+        let (action, score) = actions
             .iter()
             .copied()
-            .map(|a| (a, self.multi_run(max, a, buffer, max_depth, rng)))
-            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|a| {
+                let (score, s) = self.multi_run(&budget, config, a, buffer, rng);
+                stats += s;
+                (a, score)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        stats.elapsed = now.elapsed();
+        Some((action, score, stats))
     }
 }
@@ -0,0 +1,255 @@
+use crate::{outcome, Budget, BudgetStats, Game, Status, Stopwatch};
+
+/// Kind of value stored in the transposition table
+/// - Exact: The value of the state
+/// - Lower: The search failed high, the value is at least this one
+/// - Upper: The search failed low, the value is at most this one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: usize,
+    pub value: f32,
+    pub bound: Bound,
+    pub action: Option<usize>,
+}
+
+/// Fixed size table indexed by the state key, an entry replaces an older one
+/// searched at the same depth or less
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    /// 2^bits entries
+    pub fn new(bits: u32) -> Self {
+        Self {
+            entries: vec![None; 1 << bits],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|e| *e = None);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    pub fn get(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|e| e.key == key)
+    }
+
+    pub fn store(&mut self, entry: Entry) {
+        let i = self.index(entry.key);
+        match self.entries[i] {
+            Some(old) if old.key != entry.key || old.depth <= entry.depth => {
+                self.entries[i] = Some(entry)
+            }
+            Some(_) => (),
+            None => self.entries[i] = Some(entry),
+        }
+    }
+}
+
+/// Negamax with alpha-beta pruning for two-player zero-sum games
+///
+/// Each `update` must give the turn to the other player. Values are from the point
+/// of view of the player to move, in [-1, 1]: 1 for a win, -1 for a loss.
+/// One iteration is one depth of the iterative deepening, one node is one state visited.
+pub trait Negamax: Game {
+    /// Hash of the state for the transposition table
+    fn key(&self) -> u64;
+
+    /// Value of a leaf, the outcome of a finished game and 0 otherwise by default
+    fn evaluate(&self) -> f32 {
+        match self.status() {
+            Status::None => 0.,
+            status => 2. * outcome(status, self.turn() % Self::PLAYERS) as f32 - 1.,
+        }
+    }
+
+    /// Move ordering hook, the most promising actions first
+    /// The best action of the transposition table is then moved in front.
+    fn order(&self, _actions: &mut [usize]) {}
+
+    /// Return the value and the best action, None if the budget is over
+    fn alpha_beta(
+        &self,
+        depth: usize,
+        mut alpha: f32,
+        mut beta: f32,
+        tt: &mut TranspositionTable,
+        buffers: &mut [Vec<usize>],
+        watch: &mut Stopwatch,
+    ) -> Option<(f32, Option<usize>)> {
+        watch.add_nodes(1);
+        if watch.nodes().is_multiple_of(256) && watch.is_over() {
+            return None;
+        }
+
+        let key = self.key();
+        let alpha0 = alpha;
+        let mut tt_action = None;
+        if let Some(entry) = tt.get(key) {
+            tt_action = entry.action;
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return Some((entry.value, entry.action)),
+                    Bound::Lower => alpha = alpha.max(entry.value),
+                    Bound::Upper => beta = beta.min(entry.value),
+                }
+                if alpha >= beta {
+                    return Some((entry.value, entry.action));
+                }
+            }
+        }
+
+        let (actions, buffers) = buffers.split_first_mut().unwrap();
+        actions.clear();
+        self.fill(actions);
+        if depth == 0 || actions.is_empty() {
+            return Some((self.evaluate(), None));
+        }
+
+        self.order(actions);
+        if let Some(i) = tt_action.and_then(|a| actions.iter().position(|&b| b == a)) {
+            actions[..=i].rotate_right(1);
+        }
+
+        let mut best = (f32::NEG_INFINITY, None);
+        for &action in actions.iter() {
+            let mut child = self.clone();
+            child.update(action);
+            let (value, _) = child.alpha_beta(depth - 1, -beta, -alpha, tt, buffers, watch)?;
+            if -value > best.0 {
+                best = (-value, Some(action));
+            }
+            alpha = alpha.max(-value);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best.0 <= alpha0 {
+            Bound::Upper
+        } else if best.0 >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        tt.store(Entry {
+            key,
+            depth,
+            value: best.0,
+            bound,
+            action: best.1,
+        });
+        Some(best)
+    }
+
+    /// Search depth 1, 2, ... until the budget is over or the game is solved
+    ///
+    /// The budget iterations limit the depth. The last complete depth gives the action,
+    /// None if no depth was completed or the game is over.
+    fn iterative_deepening(
+        &self,
+        budget: &Budget,
+        tt: &mut TranspositionTable,
+    ) -> Option<(usize, f32, BudgetStats)> {
+        let mut watch = budget.start();
+        let mut buffers = Vec::new();
+        let mut best = None;
+
+        while watch.next_iteration() {
+            let depth = watch.iterations();
+            buffers.resize(depth + 1, Vec::new());
+            let inf = f32::INFINITY;
+            let Some((value, action)) =
+                self.alpha_beta(depth, -inf, inf, tt, &mut buffers, &mut watch)
+            else {
+                break;
+            };
+            best = action.map(|a| (a, value));
+
+            // Proven win or loss
+            if best.is_none() || value.abs() >= 1. {
+                break;
+            }
+        }
+
+        best.map(|(action, value)| (action, value, watch.stats()))
+    }
+}
+
+#[test]
+fn negamax() {
+    /// Take 1 to 3 stones, the player taking the last one wins
+    #[derive(Debug, Clone)]
+    struct Nim {
+        stones: usize,
+        turn: usize,
+    }
+
+    impl Game for Nim {
+        fn input<const N: usize>(&self) -> [f32; N] {
+            [self.stones as f32; N]
+        }
+        fn turn(&self) -> usize {
+            self.turn
+        }
+        fn status(&self) -> Status {
+            if self.stones == 0 {
+                Status::Win((self.turn + 1) % 2)
+            } else {
+                Status::None
+            }
+        }
+        fn reward(&self, player: usize) -> f32 {
+            outcome(self.status(), player) as f32
+        }
+        fn fill(&self, actions: &mut Vec<usize>) {
+            actions.clear();
+            actions.extend((1..=3).filter(|&n| n <= self.stones));
+        }
+        fn update(&mut self, action: usize) {
+            self.stones -= action;
+            self.turn += 1;
+        }
+    }
+
+    impl Negamax for Nim {
+        fn key(&self) -> u64 {
+            (self.stones * 2 + self.turn % 2) as u64
+        }
+    }
+
+    let mut tt = TranspositionTable::new(10);
+    let (action, value, stats) = Nim { stones: 5, turn: 0 }
+        .iterative_deepening(&Budget::iterations(10), &mut tt)
+        .unwrap();
+    assert_eq!((action, value), (1, 1.));
+    assert!(stats.iterations <= 5);
+
+    // Leave a multiple of 4
+    let (action, value, _) = Nim {
+        stones: 30,
+        turn: 1,
+    }
+    .iterative_deepening(&Budget::time(std::time::Duration::from_secs(1)), &mut tt)
+    .unwrap();
+    assert_eq!((action, value), (2, 1.));
+
+    // The opponent wins whatever the move
+    let (_, value, _) = Nim { stones: 8, turn: 0 }
+        .iterative_deepening(&Budget::iterations(20), &mut tt)
+        .unwrap();
+    assert_eq!(value, -1.);
+}
@@ -0,0 +1,252 @@
+use crate::{Budget, BudgetStats, Crossover, GaConfig, Mutation, ParentSelection};
+use rand::prelude::{SliceRandom, ThreadRng};
+use std::fmt::Debug;
+
+/// - chromosome: Genes
+/// - objectives: Scores, all maximised
+/// - rank: Index of the Pareto front, 0 is non-dominated
+/// - crowding: Distance to the neighbours in the front, infinite on the boundaries
+#[derive(Debug, Clone, Copy)]
+pub struct Solution<A, const CHR_SIZE: usize, const OBJ: usize> {
+    pub chromosome: [A; CHR_SIZE],
+    pub objectives: [i64; OBJ],
+    pub rank: usize,
+    pub crowding: f64,
+}
+
+/// At least as good on every objective and better on one
+pub fn dominates(a: &[i64], b: &[i64]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a >= b) && a.iter().zip(b).any(|(a, b)| a > b)
+}
+
+/// Indices of the points grouped by Pareto front, the non-dominated first
+pub fn non_dominated_sort<const OBJ: usize>(points: &[[i64; OBJ]]) -> Vec<Vec<usize>> {
+    // Points dominated by each point & number of points dominating it
+    let mut dominated = vec![Vec::new(); points.len()];
+    let mut count = vec![0; points.len()];
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            if dominates(&points[i], &points[j]) {
+                dominated[i].push(j);
+                count[j] += 1;
+            } else if dominates(&points[j], &points[i]) {
+                dominated[j].push(i);
+                count[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut front = (0..points.len())
+        .filter(|&i| count[i] == 0)
+        .collect::<Vec<_>>();
+    while !front.is_empty() {
+        let mut next = Vec::new();
+        for &i in &front {
+            for &j in &dominated[i] {
+                count[j] -= 1;
+                if count[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        fronts.push(std::mem::replace(&mut front, next));
+    }
+    fronts
+}
+
+/// Crowding distance of each point of the front, in the same order
+pub fn crowding_distance<const OBJ: usize>(points: &[[i64; OBJ]], front: &[usize]) -> Vec<f64> {
+    let mut distance = vec![0.; front.len()];
+    let mut order = (0..front.len()).collect::<Vec<_>>();
+
+    if front.is_empty() {
+        return distance;
+    }
+
+    (0..OBJ).for_each(|o| {
+        let value = |i: usize| points[front[i]][o];
+        order.sort_by_key(|&i| value(i));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        distance[first] = f64::INFINITY;
+        distance[last] = f64::INFINITY;
+
+        let range = (value(last) - value(first)) as f64;
+        if range == 0. {
+            return;
+        }
+        for w in order.windows(3) {
+            distance[w[1]] += (value(w[2]) - value(w[0])) as f64 / range;
+        }
+    });
+    distance
+}
+
+/// NSGA-II, multi-objective genetic algorithm
+/// - A: Action
+/// - CHR_SIZE: Chromosome size
+/// - OBJ: Number of objectives
+/// - POP_SIZE: Population size
+///
+/// Parents and survivors are ranked by front then by crowding distance,
+/// the population is kept in this order. The elitism of `GaConfig` is unused,
+/// the best fronts always survive.
+pub trait Nsga2<A: Debug + Default + Copy, const CHR_SIZE: usize, const OBJ: usize>:
+    Debug + Clone
+{
+    const POP_SIZE: usize;
+
+    /// Generate all possible alleles for each gene
+    fn fill(&self, possible_genes: &mut [Vec<A>; CHR_SIZE]);
+
+    /// Objectives to maximise
+    fn eval(&self, chromosome: &[A; CHR_SIZE]) -> [i64; OBJ];
+
+    fn clear_and_fill(&self, possible_genes: &mut [Vec<A>; CHR_SIZE]) {
+        possible_genes.iter_mut().for_each(Vec::clear);
+        self.fill(possible_genes);
+    }
+
+    fn solution(&self, chromosome: [A; CHR_SIZE]) -> Solution<A, CHR_SIZE, OBJ> {
+        Solution {
+            chromosome,
+            objectives: self.eval(&chromosome),
+            rank: 0,
+            crowding: 0.,
+        }
+    }
+
+    /// Keep the best `Self::POP_SIZE` solutions, ordered by rank and crowding distance
+    fn survive(population: &mut Vec<Solution<A, CHR_SIZE, OBJ>>) {
+        let points = population.iter().map(|s| s.objectives).collect::<Vec<_>>();
+        let mut next = Vec::with_capacity(Self::POP_SIZE);
+
+        for (rank, front) in non_dominated_sort(&points).iter().enumerate() {
+            let mut members = front
+                .iter()
+                .copied()
+                .zip(crowding_distance(&points, front))
+                .collect::<Vec<_>>();
+            members.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            for (i, crowding) in members.into_iter().take(Self::POP_SIZE - next.len()) {
+                next.push(Solution {
+                    rank,
+                    crowding,
+                    ..population[i]
+                });
+            }
+            if next.len() == Self::POP_SIZE {
+                break;
+            }
+        }
+        *population = next;
+    }
+
+    /// Return the Pareto front of the final population
+    fn nsga2<S: ParentSelection, C: Crossover<A>, M: Mutation<A>>(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &GaConfig<S, C, M>,
+        population: &mut Vec<Solution<A, CHR_SIZE, OBJ>>,
+        possible_genes: &mut [Vec<A>; CHR_SIZE],
+        budget: &Budget,
+    ) -> (Vec<Solution<A, CHR_SIZE, OBJ>>, BudgetStats) {
+        // One iteration is one generation, one node is one evaluation
+        let mut watch = budget.start();
+
+        self.clear_and_fill(possible_genes);
+
+        // Can't build chromosome without choice
+        if possible_genes.iter().map(Vec::len).sum::<usize>() == 0 {
+            return (Vec::new(), watch.stats());
+        }
+
+        population.reserve(2 * Self::POP_SIZE);
+        for _ in 0..Self::POP_SIZE {
+            let chromosome =
+                std::array::from_fn(|i| *possible_genes[i].choose(rng).unwrap_or(&A::default()));
+            population.push(self.solution(chromosome));
+        }
+        watch.add_nodes(Self::POP_SIZE);
+        Self::survive(population);
+
+        // Selection works on the order, the first front has the highest score
+        let mut ranks = Vec::with_capacity(Self::POP_SIZE);
+        while watch.next_iteration() {
+            ranks.clear();
+            ranks.extend(population.iter().map(|s| (-(s.rank as i64), ())));
+
+            for _ in 0..Self::POP_SIZE {
+                let parent0 = &population[config.selection.select(rng, &ranks)].chromosome;
+                let parent1 = &population[config.selection.select(rng, &ranks)].chromosome;
+                let mut chromosome = [A::default(); CHR_SIZE];
+                config
+                    .crossover
+                    .crossover(rng, parent0, parent1, &mut chromosome);
+                config.mutation.mutate(rng, possible_genes, &mut chromosome);
+                population.push(self.solution(chromosome));
+            }
+            watch.add_nodes(Self::POP_SIZE);
+            Self::survive(population);
+        }
+
+        let front = population
+            .iter()
+            .take_while(|s| s.rank == 0)
+            .copied()
+            .collect();
+        (front, watch.stats())
+    }
+}
+
+#[test]
+fn nsga2() {
+    use crate::{RandomReset, Tournament, Uniform};
+
+    assert_eq!(
+        non_dominated_sort(&[[1, 1], [2, 0], [0, 0], [0, 2]]),
+        vec![vec![0, 1, 3], vec![2]]
+    );
+    assert_eq!(
+        crowding_distance(&[[0, 4], [1, 2], [4, 0]], &[0, 1, 2]),
+        vec![f64::INFINITY, 2., f64::INFINITY]
+    );
+
+    /// Trade x against 10 - x, y is only a penalty
+    #[derive(Debug, Clone)]
+    struct TradeOff;
+
+    impl Nsga2<i64, 2, 2> for TradeOff {
+        const POP_SIZE: usize = 40;
+
+        fn fill(&self, possible_genes: &mut [Vec<i64>; 2]) {
+            possible_genes
+                .iter_mut()
+                .for_each(|genes| genes.extend(0..=10));
+        }
+
+        fn eval(&self, [x, y]: &[i64; 2]) -> [i64; 2] {
+            [x - y, 10 - x - y]
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let config = GaConfig::new(Tournament { size: 2 }, Uniform, RandomReset { rate: 0.1 });
+    let (front, stats) = TradeOff.nsga2(
+        &mut rng,
+        &config,
+        &mut Vec::new(),
+        &mut std::array::from_fn(|_| Vec::new()),
+        &Budget::iterations(50),
+    );
+
+    assert_eq!(stats.nodes, 40 * 51);
+    assert!(front.len() > 1);
+    assert!(front.iter().all(|s| s.chromosome[1] == 0));
+    // The front spreads over the x, usually all of them
+    let covered = (0..=10)
+        .filter(|&x| front.iter().any(|s| s.chromosome[0] == x))
+        .count();
+    assert!(covered >= 6);
+}
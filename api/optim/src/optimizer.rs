@@ -0,0 +1,328 @@
+/// Update rule of the parameters from their gradients
+/// - Momentum: beta is the decay of the velocity
+/// - RmsProp: rho is the decay of the squared gradients
+/// - AdamW: Adam with a weight decay decoupled from the gradients
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Optimizer {
+    Sgd,
+    Momentum {
+        beta: f32,
+    },
+    RmsProp {
+        rho: f32,
+        eps: f32,
+    },
+    Adam {
+        beta1: f32,
+        beta2: f32,
+        eps: f32,
+    },
+    AdamW {
+        beta1: f32,
+        beta2: f32,
+        eps: f32,
+        weight_decay: f32,
+    },
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::adam()
+    }
+}
+
+impl Optimizer {
+    pub fn momentum() -> Self {
+        Self::Momentum { beta: 0.9 }
+    }
+
+    pub fn rms_prop() -> Self {
+        Self::RmsProp {
+            rho: 0.9,
+            eps: 1e-8,
+        }
+    }
+
+    pub fn adam() -> Self {
+        Self::Adam {
+            beta1: 0.9,
+            beta2: 0.999,
+            eps: 1e-8,
+        }
+    }
+
+    pub fn adam_w(weight_decay: f32) -> Self {
+        Self::AdamW {
+            beta1: 0.9,
+            beta2: 0.999,
+            eps: 1e-8,
+            weight_decay,
+        }
+    }
+
+    /// Update one parameter tensor at step `t`, from 1
+    /// `m` and `v` are its first and second moments, as long as the parameters
+    pub fn update(
+        &self,
+        t: i32,
+        lr: f32,
+        params: &mut [f32],
+        grads: &[f32],
+        m: &mut [f32],
+        v: &mut [f32],
+    ) {
+        let all = params.iter_mut().zip(grads).zip(m.iter_mut().zip(v));
+
+        match *self {
+            Optimizer::Sgd => all.for_each(|((p, g), _)| *p -= lr * g),
+            Optimizer::Momentum { beta } => all.for_each(|((p, g), (m, _))| {
+                *m = beta * *m + g;
+                *p -= lr * *m;
+            }),
+            Optimizer::RmsProp { rho, eps } => all.for_each(|((p, g), (_, v))| {
+                *v = rho * *v + (1. - rho) * g * g;
+                *p -= lr * g / (v.sqrt() + eps);
+            }),
+            Optimizer::Adam { beta1, beta2, eps }
+            | Optimizer::AdamW {
+                beta1, beta2, eps, ..
+            } => {
+                let decay = match *self {
+                    Optimizer::AdamW { weight_decay, .. } => weight_decay,
+                    _ => 0.,
+                };
+                let c1 = 1. / (1. - beta1.powi(t));
+                let c2 = 1. / (1. - beta2.powi(t));
+                all.for_each(|((p, g), (m, v))| {
+                    *m = beta1 * *m + (1. - beta1) * g;
+                    *v = beta2 * *v + (1. - beta2) * g * g;
+                    *p -= lr * (*m * c1 / ((*v * c2).sqrt() + eps) + decay * *p);
+                })
+            }
+        }
+    }
+}
+
+/// Factor of the learning rate at step `t`, from 1
+/// - Step: Multiplied by gamma every `every` steps
+/// - Exponential: Multiplied by gamma every step
+/// - Cosine: From 1 to min in `steps` steps, then min
+/// - Warmup: Linear from 0 to 1 in `steps` steps, then 1
+///
+/// Step counts below 1 count as 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Schedule {
+    #[default]
+    Constant,
+    Step {
+        every: i32,
+        gamma: f32,
+    },
+    Exponential {
+        gamma: f32,
+    },
+    Cosine {
+        steps: i32,
+        min: f32,
+    },
+    Warmup {
+        steps: i32,
+    },
+}
+
+impl Schedule {
+    pub fn factor(&self, t: i32) -> f32 {
+        match *self {
+            Schedule::Constant => 1.,
+            Schedule::Step { every, gamma } => gamma.powi((t - 1) / every.max(1)),
+            Schedule::Exponential { gamma } => gamma.powi(t - 1),
+            Schedule::Cosine { steps, min } => {
+                let steps = steps.max(1);
+                let progress = (t - 1).min(steps) as f32 / steps as f32;
+                min + (1. - min) * (1. + (std::f32::consts::PI * progress).cos()) / 2.
+            }
+            Schedule::Warmup { steps } => (t as f32 / steps.max(1) as f32).min(1.),
+        }
+    }
+}
+
+/// Gradient clipping before the update
+/// - Value: Each gradient is clamped to [-value, value]
+/// - Norm: The gradients are scaled so that their global L2 norm is at most norm
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Clip {
+    #[default]
+    None,
+    Value(f32),
+    Norm(f32),
+}
+
+/// - optimizer: Update rule, Adam by default
+/// - lr: Learning rate before the schedule, 0.001 by default
+/// - schedule: Factor of the learning rate along the steps
+/// - clip: Gradient clipping
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizerConfig {
+    pub optimizer: Optimizer,
+    pub lr: f32,
+    pub schedule: Schedule,
+    pub clip: Clip,
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        Self {
+            optimizer: Optimizer::default(),
+            lr: 0.001,
+            schedule: Schedule::default(),
+            clip: Clip::default(),
+        }
+    }
+}
+
+/// Step counter and moments of every parameter tensor
+#[derive(Debug, Clone, Default)]
+pub struct OptimizerState {
+    pub t: i32,
+    moments: Vec<(Vec<f32>, Vec<f32>)>,
+}
+
+impl OptimizerState {
+    pub fn reset(&mut self) {
+        self.t = 0;
+        self.moments.clear();
+    }
+}
+
+impl OptimizerConfig {
+    pub fn new(optimizer: Optimizer, lr: f32) -> Self {
+        Self {
+            optimizer,
+            lr,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn with_clip(mut self, clip: Clip) -> Self {
+        self.clip = clip;
+        self
+    }
+
+    /// Learning rate at step `t`, from 1
+    pub fn lr(&self, t: i32) -> f32 {
+        self.lr * self.schedule.factor(t)
+    }
+
+    /// One step on every (parameters, gradients) tensor, the gradients are left clipped
+    /// The tensors must come in the same order at each step
+    pub fn step(&self, state: &mut OptimizerState, tensors: &mut [(&mut [f32], &mut [f32])]) {
+        match self.clip {
+            Clip::None => (),
+            Clip::Value(value) => tensors
+                .iter_mut()
+                .for_each(|(_, grads)| grads.iter_mut().for_each(|g| *g = g.clamp(-value, value))),
+            Clip::Norm(norm) => {
+                let total = tensors
+                    .iter()
+                    .map(|(_, grads)| grads.iter().map(|g| g * g).sum::<f32>())
+                    .sum::<f32>()
+                    .sqrt();
+                if total > norm {
+                    let c = norm / total;
+                    tensors
+                        .iter_mut()
+                        .for_each(|(_, grads)| grads.iter_mut().for_each(|g| *g *= c));
+                }
+            }
+        }
+
+        if state.moments.len() != tensors.len() {
+            state.moments = tensors
+                .iter()
+                .map(|(params, _)| (vec![0.; params.len()], vec![0.; params.len()]))
+                .collect();
+        }
+        state.t = state.t.saturating_add(1);
+        let lr = self.lr(state.t);
+
+        for ((params, grads), (m, v)) in tensors.iter_mut().zip(state.moments.iter_mut()) {
+            self.optimizer.update(state.t, lr, params, grads, m, v);
+        }
+    }
+}
+
+#[test]
+fn optimizer() {
+    // Minimise (x - 3)^2 + 10 (y + 1)^2
+    let run = |config: OptimizerConfig, steps: usize| {
+        let mut state = OptimizerState::default();
+        let mut x = [0f32, 0.];
+        for _ in 0..steps {
+            let mut g = [2. * (x[0] - 3.), 20. * (x[1] + 1.)];
+            config.step(&mut state, &mut [(&mut x, &mut g)]);
+        }
+        assert_eq!(state.t, steps as i32);
+        x
+    };
+
+    for (optimizer, lr) in [
+        (Optimizer::Sgd, 0.01),
+        (Optimizer::momentum(), 0.01),
+        (Optimizer::rms_prop(), 0.01),
+        (Optimizer::adam(), 0.05),
+        (Optimizer::adam_w(1e-4), 0.05),
+    ] {
+        let x = run(OptimizerConfig::new(optimizer, lr), 2000);
+        assert!(
+            (x[0] - 3.).abs() < 0.05 && (x[1] + 1.).abs() < 0.05,
+            "{optimizer:?} {x:?}"
+        );
+    }
+
+    // The first Adam step moves every parameter by the learning rate
+    let x = run(OptimizerConfig::new(Optimizer::adam(), 0.1), 1);
+    assert!((x[0] - 0.1).abs() < 1e-4 && (x[1] + 0.1).abs() < 1e-4);
+
+    // Clipped to a norm of 1 in the direction of the gradient
+    let config = OptimizerConfig::new(Optimizer::Sgd, 1.).with_clip(Clip::Norm(1.));
+    let x = run(config, 1);
+    assert!((x[0] * x[0] + x[1] * x[1] - 1.).abs() < 1e-5 && x[1] < -0.9);
+    let config = OptimizerConfig::new(Optimizer::Sgd, 1.).with_clip(Clip::Value(0.5));
+    assert_eq!(run(config, 1), [0.5, -0.5]);
+
+    assert_eq!(
+        Schedule::Step {
+            every: 10,
+            gamma: 0.5
+        }
+        .factor(21),
+        0.25
+    );
+    assert_eq!(Schedule::Warmup { steps: 4 }.factor(2), 0.5);
+    let cosine = Schedule::Cosine {
+        steps: 10,
+        min: 0.1,
+    };
+    assert_eq!(cosine.factor(1), 1.);
+    assert!((cosine.factor(11) - 0.1).abs() < 1e-6);
+    assert!((cosine.factor(100) - 0.1).abs() < 1e-6);
+
+    let config = OptimizerConfig {
+        schedule: Schedule::Step {
+            every: 0,
+            gamma: 0.5,
+        },
+        ..Default::default()
+    };
+    assert_eq!(config.lr(3), 0.001 * 0.25);
+    let config = OptimizerConfig::default().with_schedule(Schedule::Cosine { steps: 0, min: 0.1 });
+    assert_eq!(config.lr(1), 0.001);
+    assert!((config.lr(2) - 0.0001).abs() < 1e-9);
+    assert_eq!(Schedule::Warmup { steps: 0 }.factor(1), 1.);
+    assert_eq!(Schedule::Warmup { steps: -1 }.factor(2), 1.);
+}
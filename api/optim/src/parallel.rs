@@ -0,0 +1,314 @@
+use crate::{
+    Budget, BudgetStats, DefaultPolicy, Expansion, Mcts, MctsConfig, MctsFlow, MonteCarlo,
+    RolloutConfig, Score, Selection, Tree,
+};
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Merge the stats of threads run at the same time
+fn merge(stats: impl Iterator<Item = BudgetStats>, elapsed: Duration) -> BudgetStats {
+    let mut out = stats.fold(BudgetStats::default(), |mut out, stats| {
+        out += stats;
+        out
+    });
+    out.elapsed = elapsed;
+    out
+}
+
+/// Budget of each action when `n` actions are shared between `threads`
+fn per_action(budget: &Budget, n: usize, threads: usize) -> Budget {
+    Budget {
+        time: budget.split(n.div_ceil(threads.max(1))).time,
+        ..budget.split(n)
+    }
+}
+
+/// Multi-threaded `Mcts`, each thread has its own `ThreadRng`
+pub trait ParallelMcts<A: Debug + Copy + Eq + Hash + Send>: Mcts<A> + Sync {
+    /// Root parallelisation: each thread grows its own tree, they are merged into `tree`
+    fn root_parallel_mcts<S: Selection + Sync, P: DefaultPolicy<Self, A> + Sync>(
+        &self,
+        threads: usize,
+        budget: &Budget,
+        config: &MctsConfig<S, P>,
+        tree: &mut Tree<A>,
+    ) -> BudgetStats {
+        let now = Instant::now();
+        let max_nodes = tree.max_nodes();
+
+        let results = thread::scope(|scope| {
+            let handles = (0..threads)
+                .map(|thread| {
+                    let budget = budget.share(threads, thread);
+                    scope.spawn(move || {
+                        let mut rng = rand::thread_rng();
+                        let mut tree = Tree::with_max_nodes(max_nodes);
+                        let mut actions = Vec::new();
+                        let stats =
+                            self.clone()
+                                .mcts(&mut rng, &budget, config, &mut tree, &mut actions);
+                        (tree, stats)
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        results.iter().for_each(|(other, _)| tree.merge(other));
+        merge(results.into_iter().map(|(_, stats)| stats), now.elapsed())
+    }
+
+    /// Tree parallelisation: the threads share `tree` behind a lock
+    ///
+    /// Nodes on the path of a running rollout carry a virtual loss, so that the
+    /// other threads explore elsewhere. The simulation runs without the lock,
+    /// use `Expansion::One` to keep it significant. The tree is not pruned.
+    fn tree_parallel_mcts<S: Selection + Sync, P: DefaultPolicy<Self, A> + Sync>(
+        &self,
+        threads: usize,
+        budget: &Budget,
+        config: &MctsConfig<S, P>,
+        tree: &mut Tree<A>,
+        virtual_loss: f64,
+    ) -> BudgetStats {
+        let now = Instant::now();
+        let shared = Mutex::new(std::mem::take(tree));
+
+        let stats = thread::scope(|scope| {
+            let handles = (0..threads)
+                .map(|thread| {
+                    let budget = budget.share(threads, thread);
+                    let shared = &shared;
+                    scope.spawn(move || {
+                        let mut rng = rand::thread_rng();
+                        let mut actions = Vec::new();
+                        let mut path = Vec::new();
+                        let mut watch = budget.start();
+
+                        while watch.next_iteration() {
+                            let mut game = self.clone();
+                            path.clear();
+
+                            // Selection & expansion
+                            {
+                                let mut tree = shared.lock().unwrap();
+                                let mut node = 0;
+                                loop {
+                                    game.clear_and_fill(&mut actions);
+                                    tree[node].visit += 1;
+                                    tree[node].score -= virtual_loss;
+                                    if path.len() >= config.rollout.max_depth {
+                                        break;
+                                    }
+
+                                    let Some((action, child, expanded)) = tree.descend(
+                                        &mut rng,
+                                        &config.selection,
+                                        config.expansion,
+                                        node,
+                                        &actions,
+                                        |action| game.prior(action, &actions),
+                                    ) else {
+                                        break;
+                                    };
+
+                                    path.push((child, game.update(action).to_f64()));
+                                    node = child;
+
+                                    if expanded && config.expansion != Expansion::All {
+                                        tree[node].visit += 1;
+                                        tree[node].score -= virtual_loss;
+                                        break;
+                                    }
+                                }
+                            }
+
+                            // Simulation
+                            let (mut score, depth) =
+                                game.simulate(&mut rng, config, path.len(), &mut actions);
+
+                            // Backpropagation, the virtual loss is given back
+                            let mut tree = shared.lock().unwrap();
+                            for &(node, reward) in path.iter().rev() {
+                                score = reward + config.rollout.discount * score;
+                                tree[node].score += virtual_loss;
+                                tree[node].backpropagate(score);
+                            }
+                            tree[0].score += virtual_loss;
+                            tree[0].backpropagate(score);
+                            watch.add_nodes(depth + path.len());
+                        }
+                        watch.stats()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        *tree = shared.into_inner().unwrap();
+        merge(stats.into_iter(), now.elapsed())
+    }
+}
+
+impl<A: Debug + Copy + Eq + Hash + Send, G: Mcts<A> + Sync> ParallelMcts<A> for G {}
+
+/// Multi-threaded `MonteCarlo`, the actions are shared between the threads
+pub trait ParallelMonteCarlo<A: Copy + Debug + Send + Sync>: MonteCarlo<A> + Sync {
+    fn parallel_mc_play(
+        &self,
+        threads: usize,
+        budget: &Budget,
+        config: &RolloutConfig,
+    ) -> Option<(A, f64, BudgetStats)> {
+        let mut actions = Vec::new();
+        self.clear_and_fill(&mut actions);
+        if actions.is_empty() {
+            return None;
+        }
+        let now = Instant::now();
+        let budget = per_action(budget, actions.len(), threads);
+
+        let results = thread::scope(|scope| {
+            let handles = actions
+                .chunks(actions.len().div_ceil(threads.max(1)))
+                .map(|chunk| {
+                    let budget = &budget;
+                    scope.spawn(move || {
+                        let mut rng = rand::thread_rng();
+                        let mut buffer = Vec::new();
+                        chunk
+                            .iter()
+                            .map(|&a| {
+                                let (score, stats) =
+                                    self.multi_run(budget, config, a, &mut buffer, &mut rng);
+                                (a, score, stats)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let stats = merge(results.iter().map(|r| r.2), now.elapsed());
+        results
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(a, score, _)| (a, score, stats))
+    }
+}
+
+impl<A: Copy + Debug + Send + Sync, G: MonteCarlo<A> + Sync> ParallelMonteCarlo<A> for G {}
+
+/// Multi-threaded `MctsFlow`, the actions are shared between the threads
+pub trait ParallelMctsFlow<A: Debug + Copy + Send + Sync>: MctsFlow<A> + Sync {
+    fn parallel_mcts(
+        &self,
+        threads: usize,
+        budget: &Budget,
+        config: &RolloutConfig,
+        actions: &mut Vec<A>,
+        scores: &mut Vec<(Self::Score, usize)>,
+    ) -> Option<(A, f64, BudgetStats)> {
+        self.clear_and_fill(actions);
+        if actions.is_empty() {
+            return None;
+        }
+        let now = Instant::now();
+        let budget = per_action(budget, actions.len(), threads);
+
+        let results = thread::scope(|scope| {
+            let handles = actions
+                .chunks(actions.len().div_ceil(threads.max(1)))
+                .map(|chunk| {
+                    let budget = &budget;
+                    scope.spawn(move || {
+                        let mut rng = rand::thread_rng();
+                        let mut buffer = Vec::new();
+                        chunk
+                            .iter()
+                            .map(|&a| self.multi_run(&mut rng, budget, config, a, &mut buffer))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        scores.clear();
+        scores.extend(results.iter().map(|(s, stats)| (*s, stats.iterations)));
+        let stats = merge(results.into_iter().map(|(_, stats)| stats), now.elapsed());
+
+        Self::best(actions, scores).map(|(a, score)| (a, score, stats))
+    }
+}
+
+impl<A: Debug + Copy + Send + Sync, G: MctsFlow<A> + Sync> ParallelMctsFlow<A> for G {}
+
+#[test]
+fn parallel() {
+    /// Pick a digit at each turn, the score is the digit if it is even
+    #[derive(Debug, Clone)]
+    struct Digits(usize);
+
+    impl crate::Rollout<u8> for Digits {
+        type Score = i64;
+
+        fn fill(&self, actions: &mut Vec<u8>) {
+            if self.0 > 0 {
+                actions.extend(0..10)
+            }
+        }
+
+        fn update(&mut self, action: u8) -> i64 {
+            self.0 -= 1;
+            if action.is_multiple_of(2) {
+                action as i64
+            } else {
+                0
+            }
+        }
+    }
+
+    impl Mcts<u8> for Digits {}
+
+    let game = Digits(2);
+    let budget = Budget::iterations(4000);
+    let config = MctsConfig::new(crate::Ucb1 { c: 10. }, crate::RandomPolicy);
+
+    let mut tree = Tree::default();
+    let stats = game.root_parallel_mcts(4, &budget, &config, &mut tree);
+    assert_eq!(stats.iterations, 4000);
+    assert_eq!(tree.root().visit(), 4000);
+    assert_eq!(tree.best_action(), Some(8));
+
+    let config = config.with_expansion(Expansion::One);
+    let mut tree = Tree::default();
+    let stats = game.tree_parallel_mcts(4, &budget, &config, &mut tree, 10.);
+    assert_eq!(stats.iterations, 4000);
+    assert_eq!(tree.root().visit(), 4000);
+    assert_eq!(
+        tree.root().score() as i64,
+        tree.children(0)
+            .map(|(_, c)| tree[c].score() as i64)
+            .sum::<i64>()
+    );
+    assert_eq!(tree.best_action(), Some(8));
+}
@@ -0,0 +1,238 @@
+use rand::prelude::{SliceRandom, ThreadRng};
+use std::{
+    fmt::Debug,
+    ops::{Add, AddAssign},
+};
+
+/// Numeric type of the rewards
+pub trait Score:
+    Debug + Copy + Default + PartialOrd + Add<Output = Self> + AddAssign + Send + Sync
+{
+    fn to_f64(self) -> f64;
+    fn from_f64(x: f64) -> Self;
+}
+
+macro_rules! score_int {
+    ($($t:ty),*) => {$(
+        impl Score for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(x: f64) -> Self {
+                x.round() as $t
+            }
+        }
+    )*};
+}
+
+macro_rules! score_float {
+    ($($t:ty),*) => {$(
+        impl Score for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(x: f64) -> Self {
+                x as $t
+            }
+        }
+    )*};
+}
+
+score_int!(i32, i64);
+score_float!(f32, f64);
+
+/// Rollout settings shared by `MonteCarlo`, `MctsFlow` and `Mcts`
+/// - max_depth: Maximum number of updates from the state evaluated
+/// - discount: The reward of the update at depth d is weighted by discount^d
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RolloutConfig {
+    pub max_depth: usize,
+    pub discount: f64,
+}
+
+impl Default for RolloutConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            discount: 1.,
+        }
+    }
+}
+
+impl RolloutConfig {
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_discount(mut self, discount: f64) -> Self {
+        self.discount = discount;
+        self
+    }
+
+    /// Reward of an update at `depth`
+    pub fn discounted(&self, reward: f64, depth: usize) -> f64 {
+        reward * self.discount.powi(depth as i32)
+    }
+}
+
+/// System explored by the searches, implemented once for `MonteCarlo`, `MctsFlow` and `Mcts`
+/// - A: Action
+pub trait Rollout<A: Debug + Copy>: Clone {
+    type Score: Score;
+
+    /// Generate all possible actions
+    fn fill(&self, actions: &mut Vec<A>);
+
+    /// Update the system, return the reward of the action
+    fn update(&mut self, action: A) -> Self::Score;
+
+    fn clear_and_fill(&self, actions: &mut Vec<A>) {
+        actions.clear();
+        self.fill(actions)
+    }
+
+    /// Play random actions from `depth` until none remain or `max_depth` is reached
+    /// Return the discounted score, from the state at depth 0, and the number of updates
+    ///
+    /// The score stays in f64 so that integer scores are rounded once, by the caller.
+    fn random_rollout(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &RolloutConfig,
+        mut depth: usize,
+        actions: &mut Vec<A>,
+    ) -> (f64, usize) {
+        let start = depth;
+        let mut score = 0.;
+        self.clear_and_fill(actions);
+
+        while depth < config.max_depth {
+            let Some(&action) = actions.choose(rng) else {
+                break;
+            };
+            score += config.discounted(self.update(action).to_f64(), depth);
+            depth += 1;
+            self.clear_and_fill(actions);
+        }
+
+        (score, depth - start)
+    }
+}
+
+#[test]
+fn rollout() {
+    /// Count down, the reward is the value left
+    #[derive(Debug, Clone)]
+    struct Countdown(i32);
+
+    impl Rollout<()> for Countdown {
+        type Score = i32;
+
+        fn fill(&self, actions: &mut Vec<()>) {
+            if self.0 > 0 {
+                actions.push(())
+            }
+        }
+
+        fn update(&mut self, _: ()) -> i32 {
+            self.0 -= 1;
+            self.0
+        }
+    }
+
+    // The same game runs under the three searches
+    impl crate::MonteCarlo<()> for Countdown {}
+    impl crate::MctsFlow<()> for Countdown {}
+    impl crate::Mcts<()> for Countdown {}
+
+    let mut rng = rand::thread_rng();
+    let mut actions = Vec::new();
+    let config = RolloutConfig::default();
+    assert_eq!(
+        Countdown(4).random_rollout(&mut rng, &config, 0, &mut actions),
+        (6., 4)
+    );
+
+    // 3 + 2 / 2
+    let config = config.with_max_depth(2).with_discount(0.5);
+    assert_eq!(
+        Countdown(4).random_rollout(&mut rng, &config, 0, &mut actions),
+        (4., 2)
+    );
+    // Only depth 1 is left
+    assert_eq!(
+        Countdown(4).random_rollout(&mut rng, &config, 1, &mut actions),
+        (1.5, 1)
+    );
+
+    use crate::{Budget, Mcts, MctsConfig, MctsFlow, MonteCarlo, Tree};
+    let budget = Budget::iterations(10);
+    let mut buffer = Vec::new();
+
+    let (_, score, _) = Countdown(4)
+        .mc_play(&budget, &config, &mut actions, &mut buffer, &mut rng)
+        .unwrap();
+    assert_eq!(score, 4.);
+
+    let mut scores = Vec::new();
+    Countdown(4).mcts(
+        &mut rng,
+        &budget,
+        &config,
+        &mut actions,
+        &mut buffer,
+        &mut scores,
+    );
+    assert_eq!(scores, vec![(40, 10)]);
+
+    let mcts_config: MctsConfig = MctsConfig::default().with_rollout(config);
+    let mut tree = Tree::default();
+    Mcts::mcts(
+        &mut Countdown(4),
+        &mut rng,
+        &budget,
+        &mcts_config,
+        &mut tree,
+        &mut actions,
+    );
+    assert_eq!(tree.root().mean(), 4.);
+
+    /// A reward of 1 at each of the 3 steps
+    #[derive(Debug, Clone)]
+    struct Steps(i32);
+
+    impl Rollout<()> for Steps {
+        type Score = i32;
+
+        fn fill(&self, actions: &mut Vec<()>) {
+            if self.0 > 0 {
+                actions.push(())
+            }
+        }
+
+        fn update(&mut self, _: ()) -> i32 {
+            self.0 -= 1;
+            1
+        }
+    }
+
+    impl crate::MonteCarlo<()> for Steps {}
+    impl crate::MctsFlow<()> for Steps {}
+
+    // 1 + 0.4 + 0.16 rounded once, each discounted reward would round to 0
+    let config = RolloutConfig::default().with_discount(0.4);
+    let (_, score, _) = Steps(3)
+        .mc_play(&budget, &config, &mut actions, &mut buffer, &mut rng)
+        .unwrap();
+    assert_eq!(score, 2.);
+    Steps(3).mcts(
+        &mut rng,
+        &budget,
+        &config,
+        &mut actions,
+        &mut buffer,
+        &mut scores,
+    );
+    assert_eq!(scores, vec![(20, 10)]);
+}
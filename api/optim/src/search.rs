@@ -0,0 +1,244 @@
+use crate::{Budget, BudgetStats};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, BinaryHeap, HashMap, HashSet},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
+
+/// Order of the states expanded by `Search::best_first_search`
+/// - AStar: Score plus heuristic, optimal if the heuristic never underestimates
+/// - Greedy: Heuristic only
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BestFirst {
+    #[default]
+    AStar,
+    Greedy,
+}
+
+/// Rebuild the actions leading to a node, `nodes` holds the parent and the action of each node
+fn path_to<A: Copy>(nodes: &[(usize, Option<A>)], mut node: usize, path: &mut Vec<A>) {
+    path.clear();
+    while let (parent, Some(action)) = nodes[node] {
+        path.push(action);
+        node = parent;
+    }
+    path.reverse();
+}
+
+/// Deterministic tree searches maximising the sum of the rewards
+/// - A: Action
+///
+/// States with the same `key` are merged. One iteration is one depth for the beam search
+/// and one expansion for the best-first search, one node is one call to `update`.
+pub trait Search<A: Debug + Copy>: Debug + Clone + Hash {
+    /// Generate all possible actions
+    fn fill(&self, actions: &mut Vec<A>);
+
+    /// Update the system, return the reward of the action
+    fn update(&mut self, action: A) -> i64;
+
+    fn clear_and_fill(&self, actions: &mut Vec<A>) {
+        actions.clear();
+        self.fill(actions)
+    }
+
+    /// Estimation of the rewards still to come, 0 by default
+    fn heuristic(&self) -> i64 {
+        0
+    }
+
+    /// End of the best-first search, a state without action by default
+    fn is_goal(&self) -> bool {
+        let mut actions = Vec::new();
+        self.fill(&mut actions);
+        actions.is_empty()
+    }
+
+    /// Hash used to detect duplicate states, override with an incremental hash if needed
+    fn key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Keep the `width` best states of each depth, ranked by score plus heuristic
+    ///
+    /// Return the best score among the final states (without action) and the last beam,
+    /// `path` receives its actions.
+    fn beam_search(
+        &self,
+        budget: &Budget,
+        width: usize,
+        actions: &mut Vec<A>,
+        path: &mut Vec<A>,
+    ) -> (i64, BudgetStats) {
+        let mut watch = budget.start();
+        let mut nodes = vec![(0, None)];
+        let mut beam = vec![(0, self.clone(), 0)];
+        let mut candidates = Vec::new();
+        let mut seen = HashSet::new();
+        let mut best: Option<(i64, usize)> = None;
+
+        while !beam.is_empty() && watch.next_iteration() {
+            candidates.clear();
+            for (score, state, node) in beam.drain(..) {
+                state.clear_and_fill(actions);
+                if actions.is_empty() && best.is_none_or(|best| score > best.0) {
+                    best = Some((score, node));
+                }
+                for &action in actions.iter() {
+                    let mut child = state.clone();
+                    let score = score + child.update(action);
+                    nodes.push((node, Some(action)));
+                    candidates.push((score, child, nodes.len() - 1));
+                }
+            }
+            watch.add_nodes(candidates.len());
+
+            candidates.sort_by_key(|(score, state, _)| Reverse(score + state.heuristic()));
+            seen.clear();
+            for candidate in candidates.drain(..) {
+                if beam.len() == width {
+                    break;
+                }
+                if seen.insert(candidate.1.key()) {
+                    beam.push(candidate);
+                }
+            }
+        }
+
+        // Out of budget, the last beam is not finished
+        for &(score, _, node) in beam.iter() {
+            if best.is_none_or(|best| score > best.0) {
+                best = Some((score, node));
+            }
+        }
+        let (score, node) = best.unwrap_or((0, 0));
+        path_to(&nodes, node, path);
+        (score, watch.stats())
+    }
+
+    /// Expand the most promising state first until a goal is reached
+    ///
+    /// Return the score of the goal, None if no goal was found within the budget.
+    /// `path` receives the actions leading to the goal.
+    fn best_first_search(
+        &self,
+        budget: &Budget,
+        order: BestFirst,
+        actions: &mut Vec<A>,
+        path: &mut Vec<A>,
+    ) -> (Option<i64>, BudgetStats) {
+        let mut watch = budget.start();
+        let mut nodes = vec![(0, None)];
+        // States waiting in the queue, by node
+        let mut states = vec![Some((0, self.clone()))];
+        // Best score reaching each state
+        let mut scores = HashMap::from([(self.key(), 0)]);
+        let priority = |score: i64, state: &Self| match order {
+            BestFirst::AStar => score + state.heuristic(),
+            BestFirst::Greedy => state.heuristic(),
+        };
+        // Ties are broken by the oldest node
+        let mut queue = BinaryHeap::from([(priority(0, self), Reverse(0))]);
+
+        while let Some((_, Reverse(node))) = queue.pop() {
+            let (score, state) = states[node].take().unwrap();
+            if state.is_goal() {
+                path_to(&nodes, node, path);
+                return (Some(score), watch.stats());
+            }
+            if !watch.next_iteration() {
+                break;
+            }
+
+            state.clear_and_fill(actions);
+            watch.add_nodes(actions.len());
+            for &action in actions.iter() {
+                let mut child = state.clone();
+                let score = score + child.update(action);
+
+                // Skip the states already reached with a better score
+                let best = scores.entry(child.key()).or_insert(i64::MIN);
+                if *best >= score {
+                    continue;
+                }
+                *best = score;
+
+                nodes.push((node, Some(action)));
+                queue.push((priority(score, &child), Reverse(nodes.len() - 1)));
+                states.push(Some((score, child)));
+            }
+        }
+
+        path.clear();
+        (None, watch.stats())
+    }
+}
+
+#[test]
+fn search() {
+    /// Walk from the top left corner to the bottom right one, each step costs 1
+    #[derive(Debug, Clone, Hash)]
+    struct Maze(usize, usize);
+
+    const WALLS: [&str; 5] = ["..#..", ".##.#", "...#.", "#.#..", "....."];
+
+    impl Search<(isize, isize)> for Maze {
+        fn fill(&self, actions: &mut Vec<(isize, isize)>) {
+            if self.is_goal() {
+                return;
+            }
+            for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+                let (x, y) = (self.0 as isize + dx, self.1 as isize + dy);
+                if (0..5).contains(&x)
+                    && (0..5).contains(&y)
+                    && WALLS[y as usize].as_bytes()[x as usize] == b'.'
+                {
+                    actions.push((dx, dy));
+                }
+            }
+        }
+
+        fn update(&mut self, (dx, dy): (isize, isize)) -> i64 {
+            self.0 = (self.0 as isize + dx) as usize;
+            self.1 = (self.1 as isize + dy) as usize;
+            -1
+        }
+
+        fn heuristic(&self) -> i64 {
+            -((4 - self.0) as i64 + (4 - self.1) as i64)
+        }
+
+        fn is_goal(&self) -> bool {
+            (self.0, self.1) == (4, 4)
+        }
+    }
+
+    let (mut actions, mut path) = (Vec::new(), Vec::new());
+    let budget = Budget::iterations(1000);
+
+    let (score, stats) =
+        Maze(0, 0).best_first_search(&budget, BestFirst::AStar, &mut actions, &mut path);
+    assert_eq!(score, Some(-8));
+    assert_eq!(stats.stop, None);
+    let mut maze = Maze(0, 0);
+    path.iter().for_each(|&action| {
+        maze.update(action);
+    });
+    assert!(maze.is_goal());
+
+    let (score, _) = Maze(0, 0).best_first_search(
+        &Budget::iterations(2),
+        BestFirst::Greedy,
+        &mut actions,
+        &mut path,
+    );
+    assert_eq!(score, None);
+
+    // The goal is the only state without actions
+    let (score, _) = Maze(0, 0).beam_search(&Budget::iterations(30), 4, &mut actions, &mut path);
+    assert_eq!(score, -8);
+    assert_eq!(path.len(), 8);
+}
@@ -0,0 +1,307 @@
+use crate::NN;
+use rand::prelude::{SliceRandom, ThreadRng};
+
+/// Loss of an output against its target, Mse and Huber are averaged over the outputs
+/// - Mse: Mean squared error
+/// - CrossEntropy: The output holds probabilities, after a softmax
+/// - Huber: Squared error within delta of the target, linear beyond
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Loss {
+    #[default]
+    Mse,
+    CrossEntropy,
+    Huber {
+        delta: f32,
+    },
+}
+
+impl Loss {
+    /// Return the loss, `grad` receives its gradient with respect to the output
+    pub fn eval(&self, output: &[f32], target: &[f32], grad: &mut [f32]) -> f32 {
+        let n = output.len() as f32;
+        let all = output.iter().zip(target).zip(grad.iter_mut());
+
+        match *self {
+            Loss::Mse => {
+                all.map(|((y, t), g)| {
+                    *g = 2. * (y - t) / n;
+                    (y - t) * (y - t)
+                })
+                .sum::<f32>()
+                    / n
+            }
+            Loss::CrossEntropy => all
+                .map(|((y, t), g)| {
+                    let y = y.max(1e-7);
+                    *g = -t / y;
+                    -t * y.ln()
+                })
+                .sum(),
+            Loss::Huber { delta } => {
+                all.map(|((y, t), g)| {
+                    let d = y - t;
+                    *g = d.clamp(-delta, delta) / n;
+                    if d.abs() <= delta {
+                        d * d / 2.
+                    } else {
+                        delta * (d.abs() - delta / 2.)
+                    }
+                })
+                .sum::<f32>()
+                    / n
+            }
+        }
+    }
+}
+
+/// - epochs: Maximum number of passes over the training set
+/// - batch_size: Samples per optimizer step
+/// - validation: Fraction of the samples held out, chosen at random
+/// - patience: Stop after this many epochs without a better validation loss, or training
+///   loss without validation, and restore the best weights
+/// - shuffle: Shuffle the training set at each epoch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainConfig {
+    pub loss: Loss,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub validation: f32,
+    pub patience: Option<usize>,
+    pub shuffle: bool,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            loss: Loss::default(),
+            epochs: 100,
+            batch_size: 32,
+            validation: 0.,
+            patience: None,
+            shuffle: true,
+        }
+    }
+}
+
+impl TrainConfig {
+    pub fn new(loss: Loss, epochs: usize, batch_size: usize) -> Self {
+        Self {
+            loss,
+            epochs,
+            batch_size,
+            ..Default::default()
+        }
+    }
+
+    /// Clamped to [0, 1]
+    pub fn with_validation(mut self, validation: f32) -> Self {
+        self.validation = validation.clamp(0., 1.);
+        self
+    }
+
+    pub fn with_patience(mut self, patience: usize) -> Self {
+        self.patience = Some(patience);
+        self
+    }
+
+    pub fn with_shuffle(mut self, shuffle: bool) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+}
+
+/// Mean loss and accuracy of an epoch, the accuracy compares the argmax of the output and
+/// of the target
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EpochStats {
+    pub loss: f32,
+    pub accuracy: f32,
+    pub validation_loss: Option<f32>,
+    pub validation_accuracy: Option<f32>,
+}
+
+fn argmax(x: &[f32]) -> usize {
+    x.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map_or(0, |(i, _)| i)
+}
+
+impl<const IN: usize, const OUT: usize> NN<IN, OUT> {
+    /// Mean loss and accuracy over the samples `indices`
+    fn evaluate_on(
+        &mut self,
+        loss: Loss,
+        inputs: &[[f32; IN]],
+        targets: &[[f32; OUT]],
+        indices: &[usize],
+    ) -> (f32, f32) {
+        let mut grad = [0.; OUT];
+        let (mut total, mut correct) = (0., 0);
+        for &i in indices {
+            let output = self.predict(inputs[i]);
+            total += loss.eval(&output, &targets[i], &mut grad);
+            correct += (argmax(&output) == argmax(&targets[i])) as usize;
+        }
+        let n = indices.len().max(1) as f32;
+        (total / n, correct as f32 / n)
+    }
+
+    /// Mean loss and accuracy over a dataset
+    pub fn evaluate(
+        &mut self,
+        loss: Loss,
+        inputs: &[[f32; IN]],
+        targets: &[[f32; OUT]],
+    ) -> (f32, f32) {
+        let indices = (0..inputs.len()).collect::<Vec<_>>();
+        self.evaluate_on(loss, inputs, targets, &indices)
+    }
+
+    /// Mini-batch training on (inputs, targets), `history` receives the stats of each epoch
+    ///
+    /// Return the stats of the best epoch, whose weights are kept with early stopping.
+    pub fn fit(
+        &mut self,
+        rng: &mut ThreadRng,
+        config: &TrainConfig,
+        inputs: &[[f32; IN]],
+        targets: &[[f32; OUT]],
+        history: &mut Vec<EpochStats>,
+    ) -> EpochStats {
+        assert_eq!(inputs.len(), targets.len());
+        let mut train = (0..inputs.len()).collect::<Vec<_>>();
+        train.shuffle(rng);
+        let validation = config.validation.clamp(0., 1.);
+        let n_validation = (inputs.len() as f32 * validation).round() as usize;
+        let validation = train.split_off(inputs.len() - n_validation);
+
+        let mut grad = [0.; OUT];
+        let mut best: Option<(EpochStats, Vec<_>)> = None;
+        let mut since_best = 0;
+        history.clear();
+
+        for _ in 0..config.epochs {
+            if config.shuffle {
+                train.shuffle(rng);
+            }
+
+            let (mut loss, mut correct) = (0., 0);
+            for batch in train.chunks(config.batch_size.max(1)) {
+                let scale = 1. / batch.len() as f32;
+                for &i in batch {
+                    let output = self.predict(inputs[i]);
+                    loss += config.loss.eval(&output, &targets[i], &mut grad);
+                    correct += (argmax(&output) == argmax(&targets[i])) as usize;
+                    grad.iter_mut().for_each(|g| *g *= scale);
+                    self.backprop(grad);
+                }
+                self.step();
+            }
+
+            let n = train.len().max(1) as f32;
+            let mut stats = EpochStats {
+                loss: loss / n,
+                accuracy: correct as f32 / n,
+                ..Default::default()
+            };
+            if !validation.is_empty() {
+                let (loss, accuracy) = self.evaluate_on(config.loss, inputs, targets, &validation);
+                stats.validation_loss = Some(loss);
+                stats.validation_accuracy = Some(accuracy);
+            }
+            history.push(stats);
+
+            let score = stats.validation_loss.unwrap_or(stats.loss);
+            if best
+                .as_ref()
+                .is_none_or(|(best, _)| score < best.validation_loss.unwrap_or(best.loss))
+            {
+                let layers = config.patience.map(|_| self.layers.clone());
+                best = Some((stats, layers.unwrap_or_default()));
+                since_best = 0;
+            } else {
+                since_best += 1;
+                if config
+                    .patience
+                    .is_some_and(|patience| since_best >= patience)
+                {
+                    break;
+                }
+            }
+        }
+
+        match best {
+            Some((stats, layers)) => {
+                if config.patience.is_some() {
+                    self.layers = layers;
+                }
+                stats
+            }
+            None => EpochStats::default(),
+        }
+    }
+}
+
+#[test]
+fn fit() {
+    use crate::{Activation, Optimizer, OptimizerConfig};
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+
+    // XOR of the signs, with a margin around the axes
+    let mut inputs = Vec::new();
+    let mut targets = Vec::new();
+    while inputs.len() < 400 {
+        let x: [f32; 2] = [rng.gen_range(-1f32..1.), rng.gen_range(-1f32..1.)];
+        if x[0].abs() < 0.1 || x[1].abs() < 0.1 {
+            continue;
+        }
+        let class = (x[0] * x[1] > 0.) as usize;
+        let mut target = [0.; 2];
+        target[class] = 1.;
+        inputs.push(x);
+        targets.push(target);
+    }
+
+    let mut nn = NN::<2, 2>::builder()
+        .dense(16, Activation::Tanh)
+        .output(Activation::Softmax)
+        .with_optimizer(OptimizerConfig::new(Optimizer::adam(), 0.02));
+    let config = TrainConfig::new(Loss::CrossEntropy, 200, 16)
+        .with_validation(0.25)
+        .with_patience(20);
+    let mut history = Vec::new();
+    let best = nn.fit(&mut rng, &config, &inputs, &targets, &mut history);
+
+    assert!(!history.is_empty() && history.len() <= 200);
+    let min = history
+        .iter()
+        .map(|s| s.validation_loss.unwrap())
+        .fold(f32::INFINITY, f32::min);
+    assert_eq!(best.validation_loss, Some(min));
+    assert!(best.validation_accuracy.unwrap() > 0.9, "{best:?}");
+    // The best weights are restored
+    let (loss, accuracy) = nn.evaluate(Loss::CrossEntropy, &inputs, &targets);
+    assert!(accuracy > 0.9 && loss < 0.5, "{loss} {accuracy}");
+
+    assert_eq!(config.with_validation(2.).validation, 1.);
+    let config = TrainConfig {
+        epochs: 1,
+        validation: 2.,
+        ..config
+    };
+    nn.fit(&mut rng, &config, &inputs, &targets, &mut history);
+    assert!(history[0].validation_loss.is_some());
+
+    let mut grad = [0.; 2];
+    assert_eq!(Loss::Mse.eval(&[1., 2.], &[0., 0.], &mut grad), 2.5);
+    assert_eq!(grad, [1., 2.]);
+    let huber = Loss::Huber { delta: 1. };
+    assert_eq!(
+        huber.eval(&[0.5, 3.], &[0., 0.], &mut grad),
+        (0.125 + 2.5) / 2.
+    );
+    assert_eq!(grad, [0.25, 0.5]);
+}
@@ -0,0 +1,353 @@
+use crate::{Expansion, FinalMove, Selection, Ucb1};
+use rand::{prelude::ThreadRng, Rng};
+use std::fmt::Debug;
+
+/// - action: Action leading to the node, None for the root
+/// - parent, first_child, next_sibling: Indices in the tree
+/// - visit: u64,
+/// - score: f64, sum of the scores from the action leading to the node
+/// - score2: f64, sum of the squared scores
+/// - prior: f64, used by `Puct`
+#[derive(Debug, Clone)]
+pub struct Node<A> {
+    pub(crate) action: Option<A>,
+    pub(crate) parent: Option<usize>,
+    pub(crate) first_child: Option<usize>,
+    pub(crate) next_sibling: Option<usize>,
+    pub(crate) visit: u64,
+    pub(crate) score: f64,
+    pub(crate) score2: f64,
+    pub(crate) prior: f64,
+}
+
+impl<A> Default for Node<A> {
+    fn default() -> Self {
+        Self {
+            action: None,
+            parent: None,
+            first_child: None,
+            next_sibling: None,
+            visit: 0,
+            score: 0.,
+            score2: 0.,
+            prior: 1.,
+        }
+    }
+}
+
+impl<A> Node<A> {
+    pub fn visit(&self) -> u64 {
+        self.visit
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    pub fn prior(&self) -> f64 {
+        self.prior
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.score / self.visit as f64
+    }
+
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+        self.score2 / self.visit as f64 - mean * mean
+    }
+
+    pub(crate) fn backpropagate(&mut self, score: f64) {
+        self.score += score;
+        self.score2 += score * score;
+    }
+}
+
+/// Search tree stored in an arena, the root is the first node
+///
+/// When `max_nodes` is reached, the least visited nodes are pruned.
+pub struct Tree<A> {
+    nodes: Vec<Node<A>>,
+    max_nodes: usize,
+    /// Trajectory of the current rollout: node and value given by the search
+    pub(crate) path: Vec<(usize, f64)>,
+    /// Trajectory of the current game rollout: node and player who moved
+    pub(crate) moves: Vec<(usize, usize)>,
+}
+
+impl<A> Default for Tree<A> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node::default()],
+            max_nodes: usize::MAX,
+            path: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+}
+
+impl<A> std::ops::Index<usize> for Tree<A> {
+    type Output = Node<A>;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.nodes[index]
+    }
+}
+
+impl<A> std::ops::IndexMut<usize> for Tree<A> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.nodes[index]
+    }
+}
+
+impl<A: Copy + PartialEq> Tree<A> {
+    pub fn with_max_nodes(max_nodes: usize) -> Self {
+        Self {
+            max_nodes: max_nodes.max(1),
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.nodes.len() >= self.max_nodes
+    }
+
+    pub fn max_nodes(&self) -> usize {
+        self.max_nodes
+    }
+
+    pub fn root(&self) -> &Node<A> {
+        &self.nodes[0]
+    }
+
+    pub fn children(&self, node: usize) -> impl Iterator<Item = (A, usize)> + '_ {
+        std::iter::successors(self.nodes[node].first_child, |&child| {
+            self.nodes[child].next_sibling
+        })
+        .map(|child| (self.nodes[child].action.unwrap(), child))
+    }
+
+    pub fn child(&self, node: usize, action: A) -> Option<usize> {
+        self.children(node)
+            .find(|(a, _)| *a == action)
+            .map(|(_, child)| child)
+    }
+
+    /// None if the tree is full
+    pub fn add_child(&mut self, node: usize, action: A, prior: f64) -> Option<usize> {
+        if self.is_full() {
+            return None;
+        }
+        let child = self.nodes.len();
+        self.nodes.push(Node {
+            action: Some(action),
+            parent: Some(node),
+            next_sibling: self.nodes[node].first_child,
+            prior,
+            ..Default::default()
+        });
+        self.nodes[node].first_child = Some(child);
+        Some(child)
+    }
+
+    /// UCB1 with c = sqrt(2), None for the root
+    pub fn eval(&self, node: usize) -> Option<f64> {
+        let parent = self.nodes[node].parent?;
+        Some(Ucb1::default().eval(self.nodes[parent].visit, &self.nodes[node]))
+    }
+
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes[node].parent
+    }
+
+    /// Action leading to the node, None for the root
+    pub fn action(&self, node: usize) -> Option<A> {
+        self.nodes[node].action
+    }
+
+    /// Most visited child of the root
+    pub fn best_action(&self) -> Option<A> {
+        self.final_action(FinalMove::Robust)
+    }
+
+    pub fn final_action(&self, final_move: FinalMove) -> Option<A> {
+        match final_move {
+            FinalMove::Robust => self.children(0).max_by_key(|(_, child)| self[*child].visit),
+            FinalMove::Max => self
+                .children(0)
+                .max_by(|a, b| self[a.1].mean().total_cmp(&self[b.1].mean())),
+        }
+        .map(|x| x.0)
+    }
+
+    /// Child of the root with the worst mean score
+    pub fn worst_action(&self) -> Option<A> {
+        self.children(0)
+            .min_by(|a, b| self[a.1].mean().total_cmp(&self[b.1].mean()))
+            .map(|x| x.0)
+    }
+
+    /// Expand a new child or select the best one
+    /// Return the action, the child and if it was expanded
+    pub(crate) fn descend<S: Selection>(
+        &mut self,
+        rng: &mut ThreadRng,
+        selection: &S,
+        expansion: Expansion,
+        node: usize,
+        actions: &[A],
+        prior: impl FnOnce(A) -> f64,
+    ) -> Option<(A, usize, bool)> {
+        let unexpanded = actions
+            .iter()
+            .filter(|action| self.child(node, **action).is_none())
+            .count();
+
+        if unexpanded > 0 && expansion.allows(self.nodes[node].visit) && !self.is_full() {
+            let action = actions
+                .iter()
+                .copied()
+                .filter(|action| self.child(node, *action).is_none())
+                .nth(rng.gen_range(0..unexpanded))
+                .unwrap();
+            let child = self.add_child(node, action, prior(action))?;
+            return Some((action, child, true));
+        }
+
+        let parent_visit = self.nodes[node].visit;
+        self.children(node)
+            .filter(|(action, _)| actions.contains(action))
+            .max_by(|a, b| {
+                let a = selection.eval(parent_visit, &self.nodes[a.1]);
+                let b = selection.eval(parent_visit, &self.nodes[b.1]);
+                a.total_cmp(&b)
+            })
+            .map(|(action, child)| (action, child, false))
+    }
+
+    /// Add the stats of a tree grown from the same state
+    pub fn merge(&mut self, other: &Tree<A>) {
+        let mut stack = vec![(0, 0)];
+        while let Some((node, other_node)) = stack.pop() {
+            let Node {
+                visit,
+                score,
+                score2,
+                ..
+            } = other.nodes[other_node];
+            self.nodes[node].visit += visit;
+            self.nodes[node].score += score;
+            self.nodes[node].score2 += score2;
+
+            for (action, other_child) in other.children(other_node) {
+                let child = match self.child(node, action) {
+                    Some(child) => child,
+                    None => match self.add_child(node, action, other[other_child].prior) {
+                        Some(child) => child,
+                        None => continue,
+                    },
+                };
+                stack.push((child, other_child));
+            }
+        }
+    }
+
+    /// Keep the subtree of the action as the new tree, start from scratch if unknown
+    pub fn advance(&mut self, action: A) {
+        match self.child(0, action) {
+            Some(child) => self.rebuild(child, 0),
+            None => {
+                self.nodes.clear();
+                self.nodes.push(Node::default());
+            }
+        }
+    }
+
+    /// Remove the least visited nodes until at most `max_len` remain
+    ///
+    /// A node is always visited less than its parent, the tree stays connected.
+    pub fn prune(&mut self, max_len: usize) {
+        // The root alone is always kept
+        if self.nodes.len() <= max_len.max(1) {
+            return;
+        }
+        let mut visits = self.nodes[1..]
+            .iter()
+            .map(|node| node.visit)
+            .collect::<Vec<_>>();
+        visits.sort_unstable_by(|a, b| b.cmp(a));
+        let min_visit = visits[max_len.max(1) - 1] + 1;
+        self.rebuild(0, min_visit);
+    }
+
+    /// Copy the subtree of `root` keeping the nodes visited at least `min_visit` times
+    fn rebuild(&mut self, root: usize, min_visit: u64) {
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        nodes.push(Node {
+            action: None,
+            parent: None,
+            first_child: None,
+            next_sibling: None,
+            ..self.nodes[root].clone()
+        });
+
+        let mut stack = vec![(root, 0)];
+        while let Some((old, new)) = stack.pop() {
+            let mut next = self.nodes[old].first_child;
+            while let Some(old_child) = next {
+                let child = &self.nodes[old_child];
+                next = child.next_sibling;
+                if child.visit < min_visit {
+                    continue;
+                }
+                let new_child = nodes.len();
+                nodes.push(Node {
+                    parent: Some(new),
+                    first_child: None,
+                    next_sibling: nodes[new].first_child,
+                    ..child.clone()
+                });
+                nodes[new].first_child = Some(new_child);
+                stack.push((old_child, new_child));
+            }
+        }
+
+        self.nodes = nodes;
+    }
+}
+
+#[test]
+fn tree() {
+    let mut tree = Tree::<u8>::default();
+    let a = tree.add_child(0, 0, 1.).unwrap();
+    let b = tree.add_child(0, 1, 1.).unwrap();
+    let c = tree.add_child(b, 2, 1.).unwrap();
+    tree[0].visit = 10;
+    tree[a].visit = 3;
+    tree[b].visit = 7;
+    tree[c].visit = 5;
+
+    assert_eq!(tree.best_action(), Some(1));
+    assert_eq!(tree.child(b, 2), Some(c));
+
+    tree.prune(3);
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree.child(0, 0), None);
+
+    tree.advance(1);
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree.root().visit, 7);
+    assert_eq!(tree.best_action(), Some(2));
+
+    let mut tree = Tree::<u8>::with_max_nodes(1);
+    assert!(tree.is_full());
+    tree.prune(0);
+    assert_eq!(tree.len(), 1);
+}
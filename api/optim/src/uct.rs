@@ -0,0 +1,129 @@
+use crate::Node;
+use rand::prelude::{SliceRandom, ThreadRng};
+
+/// Tree policy, the child with the highest value is selected
+pub trait Selection {
+    fn eval<A>(&self, parent_visit: u64, child: &Node<A>) -> f64;
+}
+
+/// mean + c * sqrt(ln(N) / n)
+#[derive(Debug, Clone, Copy)]
+pub struct Ucb1 {
+    pub c: f64,
+}
+
+impl Default for Ucb1 {
+    fn default() -> Self {
+        Self {
+            c: std::f64::consts::SQRT_2,
+        }
+    }
+}
+
+impl Selection for Ucb1 {
+    fn eval<A>(&self, parent_visit: u64, child: &Node<A>) -> f64 {
+        let visit = child.visit() as f64;
+        child.mean() + self.c * ((parent_visit as f64).ln() / visit).sqrt()
+    }
+}
+
+/// UCB1 with the exploration bounded by the variance of the child
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ucb1Tuned;
+
+impl Selection for Ucb1Tuned {
+    fn eval<A>(&self, parent_visit: u64, child: &Node<A>) -> f64 {
+        let visit = child.visit() as f64;
+        let log = (parent_visit as f64).ln() / visit;
+        let variance = child.variance() + (2. * log).sqrt();
+        child.mean() + (log * variance.min(0.25)).sqrt()
+    }
+}
+
+/// mean + c * prior * sqrt(N) / (1 + n)
+///
+/// The priors are given by `Mcts::prior`
+#[derive(Debug, Clone, Copy)]
+pub struct Puct {
+    pub c: f64,
+}
+
+impl Default for Puct {
+    fn default() -> Self {
+        Self { c: 1. }
+    }
+}
+
+impl Selection for Puct {
+    fn eval<A>(&self, parent_visit: u64, child: &Node<A>) -> f64 {
+        let visit = child.visit() as f64;
+        child.mean() + self.c * child.prior() * (parent_visit as f64).sqrt() / (1. + visit)
+    }
+}
+
+/// When a new node is added to the tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Expansion {
+    /// Every node of the trajectory
+    #[default]
+    All,
+    /// The first new node of the trajectory, the rest is a simulation
+    One,
+    /// Like `One`, below nodes visited at least n times
+    Threshold(u64),
+}
+
+impl Expansion {
+    pub fn allows(&self, visit: u64) -> bool {
+        match self {
+            Expansion::All | Expansion::One => true,
+            Expansion::Threshold(n) => visit >= *n,
+        }
+    }
+}
+
+/// Default policy, used out of the tree
+/// - G: Game
+/// - A: Action
+pub trait DefaultPolicy<G, A> {
+    /// None ends the simulation
+    fn choose(&self, rng: &mut ThreadRng, game: &G, actions: &[A]) -> Option<A>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomPolicy;
+
+impl<G, A: Copy> DefaultPolicy<G, A> for RandomPolicy {
+    fn choose(&self, rng: &mut ThreadRng, _: &G, actions: &[A]) -> Option<A> {
+        actions.choose(rng).copied()
+    }
+}
+
+/// How the action is chosen once the search is over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FinalMove {
+    /// Most visited child
+    #[default]
+    Robust,
+    /// Child with the best mean score
+    Max,
+}
+
+#[test]
+fn selection() {
+    let child = Node::<u8> {
+        visit: 4,
+        score: 2.,
+        score2: 2.,
+        prior: 0.5,
+        ..Default::default()
+    };
+
+    let log = (16f64).ln() / 4.;
+    assert_eq!(Ucb1 { c: 1. }.eval(16, &child), 0.5 + log.sqrt());
+    assert_eq!(Puct { c: 2. }.eval(16, &child), 0.5 + 2. * 0.5 * 4. / 5.);
+    assert_eq!(
+        Ucb1Tuned.eval(16, &child),
+        0.5 + (log * (0.25f64).min(0.25 + (2. * log).sqrt())).sqrt()
+    );
+}
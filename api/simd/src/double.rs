@@ -0,0 +1,324 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+// ----------------------------------------
+// F64x2
+// ----------------------------------------
+
+/// SSE2 only
+#[derive(Clone, Copy)]
+pub struct F64x2(pub __m128d);
+
+impl F64x2 {
+    #[inline(always)]
+    pub fn new(a0: f64, a1: f64) -> Self {
+        Self(unsafe { _mm_set_pd(a1, a0) })
+    }
+
+    #[inline(always)]
+    pub fn from_array(a: [f64; 2]) -> Self {
+        Self::new(a[0], a[1])
+    }
+
+    #[inline(always)]
+    pub fn rand(rng: &mut ThreadRng) -> Self {
+        let mut a = [0.; 2];
+        rng.fill(&mut a[..]);
+        Self::from_array(a)
+    }
+
+    #[inline(always)]
+    pub fn zero() -> Self {
+        Self(unsafe { _mm_setzero_pd() })
+    }
+
+    #[inline(always)]
+    pub fn broadcast_with(a: f64) -> Self {
+        Self(unsafe { _mm_set1_pd(a) })
+    }
+
+    #[inline(always)]
+    pub fn to_array(self) -> [f64; 2] {
+        let mut a = [0.; 2];
+        unsafe { _mm_storeu_pd(a.as_mut_ptr(), self.0) };
+        a
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        Self(unsafe { _mm_max_pd(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn min(self, other: Self) -> Self {
+        Self(unsafe { _mm_min_pd(self.0, other.0) })
+    }
+
+    /// Horizontal sum in every lane
+    #[inline(always)]
+    pub fn sum(self) -> Self {
+        self + Self(unsafe { _mm_shuffle_pd::<0b01>(self.0, self.0) })
+    }
+
+    #[inline(always)]
+    pub fn sqrt(self) -> Self {
+        Self(unsafe { _mm_sqrt_pd(self.0) })
+    }
+
+    /// All the bits of a lane are set where the comparison holds
+    #[inline(always)]
+    pub fn eq_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm_cmpeq_pd(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn lt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm_cmplt_pd(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn gt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm_cmpgt_pd(self.0, other.0) })
+    }
+
+    /// Sign bit of the lane i in the bit i
+    #[inline(always)]
+    pub fn move_mask(self) -> u32 {
+        unsafe { _mm_movemask_pd(self.0) as u32 }
+    }
+}
+
+impl_op!(F64x2, Add, add, AddAssign, add_assign, _mm_add_pd);
+impl_op!(F64x2, Sub, sub, SubAssign, sub_assign, _mm_sub_pd);
+impl_op!(F64x2, Mul, mul, MulAssign, mul_assign, _mm_mul_pd);
+impl_op!(F64x2, Div, div, DivAssign, div_assign, _mm_div_pd);
+
+#[test]
+fn new_f64x2() {
+    assert_eq!(F64x2::new(0., 1.).to_array(), [0., 1.]);
+    assert_eq!(F64x2::from_array([2., 3.]).to_array(), [2., 3.]);
+    assert_eq!(F64x2::zero().to_array(), [0.; 2]);
+    assert_eq!(F64x2::broadcast_with(1.).to_array(), [1.; 2]);
+    let mut rng = rand::thread_rng();
+    assert!(F64x2::rand(&mut rng)
+        .to_array()
+        .iter()
+        .all(|x| (0.0..1.).contains(x)));
+}
+
+#[test]
+fn ops_f64x2() {
+    let a = F64x2::new(1., 2.);
+    let b = F64x2::new(4., 8.);
+    assert_eq!((a + b).to_array(), [5., 10.]);
+    assert_eq!((a - b).to_array(), [-3., -6.]);
+    assert_eq!((a * b).to_array(), [4., 16.]);
+    assert_eq!((a / b).to_array(), [0.25, 0.25]);
+
+    let mut c = a;
+    c += b;
+    c *= b;
+    c -= a;
+    c /= b;
+    assert_eq!(c.to_array(), [4.75, 9.75]);
+}
+
+#[test]
+fn max_min_f64x2() {
+    let a = F64x2::new(0., 3.);
+    let b = F64x2::new(1., 2.);
+    assert_eq!(a.max(b).to_array(), [1., 3.]);
+    assert_eq!(a.min(b).to_array(), [0., 2.]);
+}
+
+#[test]
+fn sum_f64x2() {
+    assert_eq!(F64x2::new(1., 2.).sum().to_array(), [3.; 2]);
+    assert_eq!(F64x2::new(4., 9.).sqrt().to_array(), [2., 3.]);
+}
+
+#[test]
+fn mask_f64x2() {
+    let a = F64x2::new(0., 3.);
+    let b = F64x2::new(1., 3.);
+    assert_eq!(a.eq_mask(b).move_mask(), 0b10);
+    assert_eq!(a.lt_mask(b).move_mask(), 0b01);
+    assert_eq!(a.gt_mask(b).move_mask(), 0b00);
+    assert_eq!(a.lt_mask(b).to_array()[0].to_bits(), u64::MAX);
+    assert_eq!(F64x2::new(-1., 1.).move_mask(), 0b01);
+}
+
+// ----------------------------------------
+// F64x4
+// ----------------------------------------
+
+/// Needs AVX: check `is_x86_feature_detected!` first or use `scalar::F64x4`
+#[derive(Clone, Copy)]
+pub struct F64x4(pub __m256d);
+
+impl F64x4 {
+    #[inline(always)]
+    pub fn new(a0: f64, a1: f64, a2: f64, a3: f64) -> Self {
+        Self(unsafe { _mm256_set_pd(a3, a2, a1, a0) })
+    }
+
+    #[inline(always)]
+    pub fn from_array(a: [f64; 4]) -> Self {
+        Self::new(a[0], a[1], a[2], a[3])
+    }
+
+    #[inline(always)]
+    pub fn rand(rng: &mut ThreadRng) -> Self {
+        let mut a = [0.; 4];
+        rng.fill(&mut a[..]);
+        Self::from_array(a)
+    }
+
+    #[inline(always)]
+    pub fn zero() -> Self {
+        Self(unsafe { _mm256_setzero_pd() })
+    }
+
+    #[inline(always)]
+    pub fn broadcast_with(a: f64) -> Self {
+        Self(unsafe { _mm256_set1_pd(a) })
+    }
+
+    #[inline(always)]
+    pub fn to_array(self) -> [f64; 4] {
+        let mut a = [0.; 4];
+        unsafe { _mm256_storeu_pd(a.as_mut_ptr(), self.0) };
+        a
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        Self(unsafe { _mm256_max_pd(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn min(self, other: Self) -> Self {
+        Self(unsafe { _mm256_min_pd(self.0, other.0) })
+    }
+
+    /// Horizontal sum in every lane, (a0 + a1) + (a2 + a3)
+    #[inline(always)]
+    pub fn sum(self) -> Self {
+        let a0 = self + Self(unsafe { _mm256_permute_pd::<0b0101>(self.0) });
+        a0 + Self(unsafe { _mm256_permute2f128_pd::<0x01>(a0.0, a0.0) })
+    }
+
+    #[inline(always)]
+    pub fn sqrt(self) -> Self {
+        Self(unsafe { _mm256_sqrt_pd(self.0) })
+    }
+
+    /// All the bits of a lane are set where the comparison holds
+    #[inline(always)]
+    pub fn eq_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm256_cmp_pd::<_CMP_EQ_OQ>(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn lt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm256_cmp_pd::<_CMP_LT_OQ>(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn gt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm256_cmp_pd::<_CMP_GT_OQ>(self.0, other.0) })
+    }
+
+    /// Sign bit of the lane i in the bit i
+    #[inline(always)]
+    pub fn move_mask(self) -> u32 {
+        unsafe { _mm256_movemask_pd(self.0) as u32 }
+    }
+}
+
+impl_op!(F64x4, Add, add, AddAssign, add_assign, _mm256_add_pd);
+impl_op!(F64x4, Sub, sub, SubAssign, sub_assign, _mm256_sub_pd);
+impl_op!(F64x4, Mul, mul, MulAssign, mul_assign, _mm256_mul_pd);
+impl_op!(F64x4, Div, div, DivAssign, div_assign, _mm256_div_pd);
+
+#[test]
+fn new_f64x4() {
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    assert_eq!(F64x4::new(0., 1., 2., 3.).to_array(), [0., 1., 2., 3.]);
+    assert_eq!(
+        F64x4::from_array([3., 2., 1., 0.]).to_array(),
+        [3., 2., 1., 0.]
+    );
+    assert_eq!(F64x4::zero().to_array(), [0.; 4]);
+    assert_eq!(F64x4::broadcast_with(1.).to_array(), [1.; 4]);
+    let mut rng = rand::thread_rng();
+    assert!(F64x4::rand(&mut rng)
+        .to_array()
+        .iter()
+        .all(|x| (0.0..1.).contains(x)));
+}
+
+#[test]
+fn ops_f64x4() {
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    let a = F64x4::new(1., 2., 3., 4.);
+    let b = F64x4::new(4., 8., 2., 1.);
+    assert_eq!((a + b).to_array(), [5., 10., 5., 5.]);
+    assert_eq!((a - b).to_array(), [-3., -6., 1., 3.]);
+    assert_eq!((a * b).to_array(), [4., 16., 6., 4.]);
+    assert_eq!((a / b).to_array(), [0.25, 0.25, 1.5, 4.]);
+
+    let mut c = a;
+    c += b;
+    c *= b;
+    c -= a;
+    c /= b;
+    assert_eq!(c.to_array(), [4.75, 9.75, 3.5, 1.]);
+}
+
+#[test]
+fn max_min_f64x4() {
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    let a = F64x4::new(0., 3., 5., -1.);
+    let b = F64x4::new(1., 2., 5., -2.);
+    assert_eq!(a.max(b).to_array(), [1., 3., 5., -1.]);
+    assert_eq!(a.min(b).to_array(), [0., 2., 5., -2.]);
+}
+
+#[test]
+fn sum_f64x4() {
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    assert_eq!(F64x4::new(1., 2., 3., 4.).sum().to_array(), [10.; 4]);
+    assert_eq!(
+        F64x4::new(4., 9., 16., 0.25).sqrt().to_array(),
+        [2., 3., 4., 0.5]
+    );
+}
+
+#[test]
+fn mask_f64x4() {
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    let a = F64x4::new(0., 3., 2., f64::NAN);
+    let b = F64x4::new(1., 3., 1., 0.);
+    assert_eq!(a.eq_mask(b).move_mask(), 0b0010);
+    assert_eq!(a.lt_mask(b).move_mask(), 0b0001);
+    assert_eq!(a.gt_mask(b).move_mask(), 0b0100);
+    assert_eq!(a.gt_mask(b).to_array()[2].to_bits(), u64::MAX);
+    assert_eq!(F64x4::new(-1., 1., -0., 0.).move_mask(), 0b0101);
+}
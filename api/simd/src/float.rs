@@ -19,6 +19,7 @@ const EPSILON: f32 = 1e-6;
 // F32x4
 // ----------------------------------------
 
+/// SSE only
 #[derive(Clone, Copy)]
 pub struct F32x4(pub __m128);
 
@@ -80,7 +81,8 @@ impl F32x4 {
 
     #[inline(always)]
     pub fn permute<const MASK: i32>(self) -> Self {
-        Self(unsafe { _mm_permute_ps::<MASK>(self.0) })
+        // Same as `_mm_permute_ps`, which needs AVX
+        Self(unsafe { _mm_shuffle_ps::<MASK>(self.0, self.0) })
     }
 
     #[inline(always)]
@@ -415,6 +417,8 @@ fn rsqrt_f32x4() {
 // F32x8
 // ----------------------------------------
 
+/// Needs AVX, and FMA for `madd`: check `is_x86_feature_detected!` first or use
+/// `scalar::F32x8`
 #[derive(Clone, Copy)]
 pub struct F32x8(pub __m256);
 
@@ -0,0 +1,786 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use core::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Mul, MulAssign,
+    Sub, SubAssign,
+};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+// ----------------------------------------
+// I32x4
+// ----------------------------------------
+
+/// SSE2 only, the arithmetic wraps
+#[derive(Clone, Copy)]
+pub struct I32x4(pub __m128i);
+
+/// Low 32 bits of the products, `_mm_mullo_epi32` needs SSE4.1
+#[inline(always)]
+unsafe fn mullo_epi32(a: __m128i, b: __m128i) -> __m128i {
+    let even = _mm_mul_epu32(a, b);
+    let odd = _mm_mul_epu32(_mm_srli_epi64::<32>(a), _mm_srli_epi64::<32>(b));
+    _mm_unpacklo_epi32(
+        _mm_shuffle_epi32::<0b00_00_10_00>(even),
+        _mm_shuffle_epi32::<0b00_00_10_00>(odd),
+    )
+}
+
+/// `a` where `mask` is set, `b` elsewhere
+#[inline(always)]
+unsafe fn select_si128(mask: __m128i, a: __m128i, b: __m128i) -> __m128i {
+    _mm_or_si128(_mm_and_si128(mask, a), _mm_andnot_si128(mask, b))
+}
+
+impl I32x4 {
+    #[inline(always)]
+    pub fn new(a0: i32, a1: i32, a2: i32, a3: i32) -> Self {
+        Self(unsafe { _mm_set_epi32(a3, a2, a1, a0) })
+    }
+
+    #[inline(always)]
+    pub fn from_array(a: [i32; 4]) -> Self {
+        Self::new(a[0], a[1], a[2], a[3])
+    }
+
+    #[inline(always)]
+    pub fn rand(rng: &mut ThreadRng) -> Self {
+        let mut a = [0; 4];
+        rng.fill(&mut a[..]);
+        Self::from_array(a)
+    }
+
+    #[inline(always)]
+    pub fn zero() -> Self {
+        Self(unsafe { _mm_setzero_si128() })
+    }
+
+    #[inline(always)]
+    pub fn broadcast_with(a: i32) -> Self {
+        Self(unsafe { _mm_set1_epi32(a) })
+    }
+
+    #[inline(always)]
+    pub fn to_array(self) -> [i32; 4] {
+        let mut a = [0; 4];
+        unsafe { _mm_storeu_si128(a.as_mut_ptr() as _, self.0) };
+        a
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        Self(unsafe { select_si128(_mm_cmpgt_epi32(self.0, other.0), self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn min(self, other: Self) -> Self {
+        Self(unsafe { select_si128(_mm_cmpgt_epi32(self.0, other.0), other.0, self.0) })
+    }
+
+    /// Horizontal sum in every lane
+    #[inline(always)]
+    pub fn sum(self) -> Self {
+        let a0 = self + Self(unsafe { _mm_shuffle_epi32::<0b10_11_00_01>(self.0) });
+        a0 + Self(unsafe { _mm_shuffle_epi32::<0b01_00_11_10>(a0.0) })
+    }
+
+    /// Every bit of a lane is set where the comparison holds
+    #[inline(always)]
+    pub fn eq_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm_cmpeq_epi32(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn lt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm_cmplt_epi32(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn gt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm_cmpgt_epi32(self.0, other.0) })
+    }
+
+    /// Sign bit of the lane i in the bit i
+    #[inline(always)]
+    pub fn move_mask(self) -> u32 {
+        unsafe { _mm_movemask_ps(_mm_castsi128_ps(self.0)) as u32 }
+    }
+
+    /// Zero from 32
+    #[inline(always)]
+    pub fn shl<const N: i32>(self) -> Self {
+        Self(unsafe { _mm_slli_epi32::<N>(self.0) })
+    }
+
+    /// Arithmetic, the sign fills the lane from 32
+    #[inline(always)]
+    pub fn shr<const N: i32>(self) -> Self {
+        Self(unsafe { _mm_srai_epi32::<N>(self.0) })
+    }
+}
+
+impl_op!(I32x4, Add, add, AddAssign, add_assign, _mm_add_epi32);
+impl_op!(I32x4, Sub, sub, SubAssign, sub_assign, _mm_sub_epi32);
+impl_op!(I32x4, Mul, mul, MulAssign, mul_assign, mullo_epi32);
+impl_op!(
+    I32x4,
+    BitAnd,
+    bitand,
+    BitAndAssign,
+    bitand_assign,
+    _mm_and_si128
+);
+impl_op!(I32x4, BitOr, bitor, BitOrAssign, bitor_assign, _mm_or_si128);
+impl_op!(
+    I32x4,
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    _mm_xor_si128
+);
+
+#[test]
+fn new_i32x4() {
+    assert_eq!(I32x4::new(0, 1, 2, 3).to_array(), [0, 1, 2, 3]);
+    assert_eq!(I32x4::from_array([3, 2, 1, 0]).to_array(), [3, 2, 1, 0]);
+    assert_eq!(I32x4::zero().to_array(), [0; 4]);
+    assert_eq!(I32x4::broadcast_with(-1).to_array(), [-1; 4]);
+    let mut rng = rand::thread_rng();
+    let _ = I32x4::rand(&mut rng);
+}
+
+#[test]
+fn ops_i32x4() {
+    let a = I32x4::new(1, -2, 3, i32::MAX);
+    let b = I32x4::new(4, 8, -3, 1);
+    assert_eq!((a + b).to_array(), [5, 6, 0, i32::MIN]);
+    assert_eq!((a - b).to_array(), [-3, -10, 6, i32::MAX - 1]);
+    assert_eq!((a * b).to_array(), [4, -16, -9, i32::MAX]);
+    assert_eq!(
+        (I32x4::broadcast_with(0x10000) * I32x4::new(0x10000, 3, -5, 0x7fff)).to_array(),
+        [0, 0x30000, -0x50000, 0x7fff0000]
+    );
+    assert_eq!((a & b).to_array(), [0, 8, 1, 1]);
+    assert_eq!((a | b).to_array(), [5, -2, -1, i32::MAX]);
+    assert_eq!((a ^ b).to_array(), [5, -10, -2, i32::MAX - 1]);
+
+    let mut c = a;
+    c += b;
+    c *= b;
+    c -= a;
+    c &= I32x4::broadcast_with(0xff);
+    c |= I32x4::broadcast_with(0x100);
+    c ^= I32x4::broadcast_with(1);
+    assert_eq!(c.to_array(), [0x112, 0x133, 0x1fc, 0x100]);
+}
+
+#[test]
+fn max_min_i32x4() {
+    let a = I32x4::new(0, 3, -5, i32::MIN);
+    let b = I32x4::new(1, 2, -4, i32::MAX);
+    assert_eq!(a.max(b).to_array(), [1, 3, -4, i32::MAX]);
+    assert_eq!(a.min(b).to_array(), [0, 2, -5, i32::MIN]);
+}
+
+#[test]
+fn sum_i32x4() {
+    assert_eq!(I32x4::new(1, 2, 3, -4).sum().to_array(), [2; 4]);
+    assert_eq!(
+        I32x4::new(i32::MAX, 1, 0, 0).sum().to_array(),
+        [i32::MIN; 4]
+    );
+}
+
+#[test]
+fn mask_i32x4() {
+    let a = I32x4::new(0, 3, 2, -1);
+    let b = I32x4::new(1, 3, 1, 0);
+    assert_eq!(a.eq_mask(b).to_array(), [0, -1, 0, 0]);
+    assert_eq!(a.lt_mask(b).move_mask(), 0b1001);
+    assert_eq!(a.gt_mask(b).move_mask(), 0b0100);
+    assert_eq!(a.move_mask(), 0b1000);
+}
+
+#[test]
+fn shift_i32x4() {
+    let a = I32x4::new(1, -8, 3, i32::MIN);
+    assert_eq!(a.shl::<2>().to_array(), [4, -32, 12, 0]);
+    assert_eq!(a.shr::<2>().to_array(), [0, -2, 0, i32::MIN / 4]);
+    assert_eq!(a.shl::<32>().to_array(), [0; 4]);
+    assert_eq!(a.shr::<32>().to_array(), [0, -1, 0, -1]);
+}
+
+// ----------------------------------------
+// I32x8
+// ----------------------------------------
+
+/// Needs AVX2: check `is_x86_feature_detected!` first or use `scalar::I32x8`
+#[derive(Clone, Copy)]
+pub struct I32x8(pub __m256i);
+
+impl I32x8 {
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    pub fn new(a0: i32, a1: i32, a2: i32, a3: i32, a4: i32, a5: i32, a6: i32, a7: i32) -> Self {
+        Self(unsafe { _mm256_set_epi32(a7, a6, a5, a4, a3, a2, a1, a0) })
+    }
+
+    #[inline(always)]
+    pub fn from_array(a: [i32; 8]) -> Self {
+        Self::new(a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7])
+    }
+
+    #[inline(always)]
+    pub fn rand(rng: &mut ThreadRng) -> Self {
+        let mut a = [0; 8];
+        rng.fill(&mut a[..]);
+        Self::from_array(a)
+    }
+
+    #[inline(always)]
+    pub fn zero() -> Self {
+        Self(unsafe { _mm256_setzero_si256() })
+    }
+
+    #[inline(always)]
+    pub fn broadcast_with(a: i32) -> Self {
+        Self(unsafe { _mm256_set1_epi32(a) })
+    }
+
+    #[inline(always)]
+    pub fn to_array(self) -> [i32; 8] {
+        let mut a = [0; 8];
+        unsafe { _mm256_storeu_si256(a.as_mut_ptr() as _, self.0) };
+        a
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        Self(unsafe { _mm256_max_epi32(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn min(self, other: Self) -> Self {
+        Self(unsafe { _mm256_min_epi32(self.0, other.0) })
+    }
+
+    /// Horizontal sum in every lane
+    #[inline(always)]
+    pub fn sum(self) -> Self {
+        let a0 = self + Self(unsafe { _mm256_shuffle_epi32::<0b10_11_00_01>(self.0) });
+        let a1 = a0 + Self(unsafe { _mm256_shuffle_epi32::<0b01_00_11_10>(a0.0) });
+        a1 + Self(unsafe { _mm256_permute2x128_si256::<0x01>(a1.0, a1.0) })
+    }
+
+    /// Every bit of a lane is set where the comparison holds
+    #[inline(always)]
+    pub fn eq_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm256_cmpeq_epi32(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn lt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm256_cmpgt_epi32(other.0, self.0) })
+    }
+
+    #[inline(always)]
+    pub fn gt_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm256_cmpgt_epi32(self.0, other.0) })
+    }
+
+    /// Sign bit of the lane i in the bit i
+    #[inline(always)]
+    pub fn move_mask(self) -> u32 {
+        unsafe { _mm256_movemask_ps(_mm256_castsi256_ps(self.0)) as u32 }
+    }
+
+    /// Zero from 32
+    #[inline(always)]
+    pub fn shl<const N: i32>(self) -> Self {
+        Self(unsafe { _mm256_slli_epi32::<N>(self.0) })
+    }
+
+    /// Arithmetic, the sign fills the lane from 32
+    #[inline(always)]
+    pub fn shr<const N: i32>(self) -> Self {
+        Self(unsafe { _mm256_srai_epi32::<N>(self.0) })
+    }
+}
+
+impl_op!(I32x8, Add, add, AddAssign, add_assign, _mm256_add_epi32);
+impl_op!(I32x8, Sub, sub, SubAssign, sub_assign, _mm256_sub_epi32);
+impl_op!(I32x8, Mul, mul, MulAssign, mul_assign, _mm256_mullo_epi32);
+impl_op!(
+    I32x8,
+    BitAnd,
+    bitand,
+    BitAndAssign,
+    bitand_assign,
+    _mm256_and_si256
+);
+impl_op!(
+    I32x8,
+    BitOr,
+    bitor,
+    BitOrAssign,
+    bitor_assign,
+    _mm256_or_si256
+);
+impl_op!(
+    I32x8,
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    _mm256_xor_si256
+);
+
+#[test]
+fn new_i32x8() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = [0, 1, 2, 3, 4, 5, 6, 7];
+    assert_eq!(I32x8::new(0, 1, 2, 3, 4, 5, 6, 7).to_array(), a);
+    assert_eq!(I32x8::from_array(a).to_array(), a);
+    assert_eq!(I32x8::zero().to_array(), [0; 8]);
+    assert_eq!(I32x8::broadcast_with(-1).to_array(), [-1; 8]);
+    let mut rng = rand::thread_rng();
+    let _ = I32x8::rand(&mut rng);
+}
+
+#[test]
+fn ops_i32x8() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = I32x8::new(1, -2, 3, i32::MAX, 0, 5, -6, 7);
+    let b = I32x8::new(4, 8, -3, 1, 2, 2, 2, -1);
+    assert_eq!((a + b).to_array(), [5, 6, 0, i32::MIN, 2, 7, -4, 6]);
+    assert_eq!((a - b).to_array(), [-3, -10, 6, i32::MAX - 1, -2, 3, -8, 8]);
+    assert_eq!((a * b).to_array(), [4, -16, -9, i32::MAX, 0, 10, -12, -7]);
+    assert_eq!((a & b).to_array(), [0, 8, 1, 1, 0, 0, 2, 7]);
+    assert_eq!((a | b).to_array(), [5, -2, -1, i32::MAX, 2, 7, -6, -1]);
+    assert_eq!((a ^ b).to_array(), [5, -10, -2, i32::MAX - 1, 2, 7, -8, -8]);
+
+    let mut c = a;
+    c += b;
+    c *= b;
+    c -= a;
+    c &= I32x8::broadcast_with(0xff);
+    c |= I32x8::broadcast_with(0x100);
+    c ^= I32x8::broadcast_with(1);
+    assert_eq!(
+        c.to_array(),
+        [0x112, 0x133, 0x1fc, 0x100, 0x105, 0x108, 0x1ff, 0x1f2]
+    );
+}
+
+#[test]
+fn max_min_i32x8() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = I32x8::new(0, 3, -5, i32::MIN, 1, 1, 7, -7);
+    let b = I32x8::new(1, 2, -4, i32::MAX, 1, 0, 8, -8);
+    assert_eq!(a.max(b).to_array(), [1, 3, -4, i32::MAX, 1, 1, 8, -7]);
+    assert_eq!(a.min(b).to_array(), [0, 2, -5, i32::MIN, 1, 0, 7, -8]);
+}
+
+#[test]
+fn sum_i32x8() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = I32x8::new(1, 2, 3, -4, 5, 6, 7, 8);
+    assert_eq!(a.sum().to_array(), [28; 8]);
+}
+
+#[test]
+fn mask_i32x8() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = I32x8::new(0, 3, 2, -1, 5, 5, 5, 5);
+    let b = I32x8::new(1, 3, 1, 0, 4, 5, 6, 5);
+    assert_eq!(a.eq_mask(b).to_array(), [0, -1, 0, 0, 0, -1, 0, -1]);
+    assert_eq!(a.lt_mask(b).move_mask(), 0b0100_1001);
+    assert_eq!(a.gt_mask(b).move_mask(), 0b0001_0100);
+    assert_eq!(a.move_mask(), 0b0000_1000);
+}
+
+#[test]
+fn shift_i32x8() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = I32x8::new(1, -8, 3, i32::MIN, 0, 1, 2, 3);
+    assert_eq!(a.shl::<2>().to_array(), [4, -32, 12, 0, 0, 4, 8, 12]);
+    assert_eq!(
+        a.shr::<2>().to_array(),
+        [0, -2, 0, i32::MIN / 4, 0, 0, 0, 0]
+    );
+}
+
+// ----------------------------------------
+// U8x16
+// ----------------------------------------
+
+/// SSE2 only, the arithmetic wraps unless saturating
+#[derive(Clone, Copy)]
+pub struct U8x16(pub __m128i);
+
+impl U8x16 {
+    #[inline(always)]
+    pub fn from_array(a: [u8; 16]) -> Self {
+        Self(unsafe { _mm_loadu_si128(a.as_ptr() as _) })
+    }
+
+    #[inline(always)]
+    pub fn rand(rng: &mut ThreadRng) -> Self {
+        let mut a = [0; 16];
+        rng.fill(&mut a[..]);
+        Self::from_array(a)
+    }
+
+    #[inline(always)]
+    pub fn zero() -> Self {
+        Self(unsafe { _mm_setzero_si128() })
+    }
+
+    #[inline(always)]
+    pub fn broadcast_with(a: u8) -> Self {
+        Self(unsafe { _mm_set1_epi8(a as i8) })
+    }
+
+    #[inline(always)]
+    pub fn to_array(self) -> [u8; 16] {
+        let mut a = [0; 16];
+        unsafe { _mm_storeu_si128(a.as_mut_ptr() as _, self.0) };
+        a
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        Self(unsafe { _mm_max_epu8(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn min(self, other: Self) -> Self {
+        Self(unsafe { _mm_min_epu8(self.0, other.0) })
+    }
+
+    /// Horizontal sum, which doesn't fit a lane
+    #[inline(always)]
+    pub fn sum(self) -> u32 {
+        unsafe {
+            let s = _mm_sad_epu8(self.0, _mm_setzero_si128());
+            (_mm_cvtsi128_si32(s) + _mm_cvtsi128_si32(_mm_srli_si128::<8>(s))) as u32
+        }
+    }
+
+    #[inline(always)]
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self(unsafe { _mm_adds_epu8(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(unsafe { _mm_subs_epu8(self.0, other.0) })
+    }
+
+    /// Every bit of a lane is set where the comparison holds
+    #[inline(always)]
+    pub fn eq_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm_cmpeq_epi8(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn lt_mask(self, other: Self) -> Self {
+        other.gt_mask(self)
+    }
+
+    /// Unsigned, `self` isn't the minimum
+    #[inline(always)]
+    pub fn gt_mask(self, other: Self) -> Self {
+        self.min(other).eq_mask(self) ^ Self::broadcast_with(u8::MAX)
+    }
+
+    /// High bit of the lane i in the bit i
+    #[inline(always)]
+    pub fn move_mask(self) -> u32 {
+        unsafe { _mm_movemask_epi8(self.0) as u32 }
+    }
+}
+
+impl_op!(U8x16, Add, add, AddAssign, add_assign, _mm_add_epi8);
+impl_op!(U8x16, Sub, sub, SubAssign, sub_assign, _mm_sub_epi8);
+impl_op!(
+    U8x16,
+    BitAnd,
+    bitand,
+    BitAndAssign,
+    bitand_assign,
+    _mm_and_si128
+);
+impl_op!(U8x16, BitOr, bitor, BitOrAssign, bitor_assign, _mm_or_si128);
+impl_op!(
+    U8x16,
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    _mm_xor_si128
+);
+
+#[cfg(test)]
+const BYTES: [u8; 32] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 100, 127, 128, 129, 200, 250, 255, 0,
+    0, 0, 0, 1, 1, 1, 1,
+];
+
+#[test]
+fn new_u8x16() {
+    let a: [u8; 16] = BYTES[..16].try_into().unwrap();
+    assert_eq!(U8x16::from_array(a).to_array(), a);
+    assert_eq!(U8x16::zero().to_array(), [0; 16]);
+    assert_eq!(U8x16::broadcast_with(200).to_array(), [200; 16]);
+    let mut rng = rand::thread_rng();
+    let _ = U8x16::rand(&mut rng);
+}
+
+#[test]
+fn ops_u8x16() {
+    let a = U8x16::from_array(BYTES[16..].try_into().unwrap());
+    let b = U8x16::broadcast_with(100);
+    let expected = |f: fn(u8, u8) -> u8| BYTES[16..].iter().map(|&x| f(x, 100)).collect::<Vec<_>>();
+    assert_eq!((a + b).to_array().to_vec(), expected(u8::wrapping_add));
+    assert_eq!((a - b).to_array().to_vec(), expected(u8::wrapping_sub));
+    assert_eq!((a & b).to_array().to_vec(), expected(|x, y| x & y));
+    assert_eq!((a | b).to_array().to_vec(), expected(|x, y| x | y));
+    assert_eq!((a ^ b).to_array().to_vec(), expected(|x, y| x ^ y));
+    assert_eq!(
+        a.saturating_add(b).to_array().to_vec(),
+        expected(u8::saturating_add)
+    );
+    assert_eq!(
+        a.saturating_sub(b).to_array().to_vec(),
+        expected(u8::saturating_sub)
+    );
+    assert_eq!(a.max(b).to_array().to_vec(), expected(u8::max));
+    assert_eq!(a.min(b).to_array().to_vec(), expected(u8::min));
+
+    let mut c = a;
+    c += b;
+    c -= b;
+    c ^= b;
+    c &= b;
+    c |= U8x16::broadcast_with(1);
+    assert_eq!(c.to_array()[..4], [101, 1, 1, 101]);
+}
+
+#[test]
+fn sum_u8x16() {
+    assert_eq!(U8x16::broadcast_with(255).sum(), 16 * 255);
+    let a = U8x16::from_array(BYTES[..16].try_into().unwrap());
+    assert_eq!(a.sum(), 120);
+}
+
+#[test]
+fn mask_u8x16() {
+    let a = U8x16::from_array(BYTES[16..].try_into().unwrap());
+    let b = U8x16::broadcast_with(128);
+    assert_eq!(a.eq_mask(b).move_mask(), 0b1000);
+    assert_eq!(a.lt_mask(b).move_mask(), 0b1111_1111_0000_0111);
+    assert_eq!(a.gt_mask(b).move_mask(), 0b0000_0000_1111_0000);
+    assert_eq!(a.eq_mask(b).to_array()[3], 255);
+    assert_eq!(a.move_mask(), 0b0000_0000_1111_1000);
+}
+
+// ----------------------------------------
+// U8x32
+// ----------------------------------------
+
+/// Needs AVX2: check `is_x86_feature_detected!` first or use `scalar::U8x32`
+#[derive(Clone, Copy)]
+pub struct U8x32(pub __m256i);
+
+impl U8x32 {
+    #[inline(always)]
+    pub fn from_array(a: [u8; 32]) -> Self {
+        Self(unsafe { _mm256_loadu_si256(a.as_ptr() as _) })
+    }
+
+    #[inline(always)]
+    pub fn rand(rng: &mut ThreadRng) -> Self {
+        let mut a = [0; 32];
+        rng.fill(&mut a[..]);
+        Self::from_array(a)
+    }
+
+    #[inline(always)]
+    pub fn zero() -> Self {
+        Self(unsafe { _mm256_setzero_si256() })
+    }
+
+    #[inline(always)]
+    pub fn broadcast_with(a: u8) -> Self {
+        Self(unsafe { _mm256_set1_epi8(a as i8) })
+    }
+
+    #[inline(always)]
+    pub fn to_array(self) -> [u8; 32] {
+        let mut a = [0; 32];
+        unsafe { _mm256_storeu_si256(a.as_mut_ptr() as _, self.0) };
+        a
+    }
+
+    #[inline(always)]
+    pub fn max(self, other: Self) -> Self {
+        Self(unsafe { _mm256_max_epu8(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn min(self, other: Self) -> Self {
+        Self(unsafe { _mm256_min_epu8(self.0, other.0) })
+    }
+
+    /// Horizontal sum, which doesn't fit a lane
+    #[inline(always)]
+    pub fn sum(self) -> u32 {
+        let mut s = [0u64; 4];
+        unsafe {
+            let sad = _mm256_sad_epu8(self.0, _mm256_setzero_si256());
+            _mm256_storeu_si256(s.as_mut_ptr() as _, sad);
+        }
+        s.iter().sum::<u64>() as u32
+    }
+
+    #[inline(always)]
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self(unsafe { _mm256_adds_epu8(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(unsafe { _mm256_subs_epu8(self.0, other.0) })
+    }
+
+    /// Every bit of a lane is set where the comparison holds
+    #[inline(always)]
+    pub fn eq_mask(self, other: Self) -> Self {
+        Self(unsafe { _mm256_cmpeq_epi8(self.0, other.0) })
+    }
+
+    #[inline(always)]
+    pub fn lt_mask(self, other: Self) -> Self {
+        other.gt_mask(self)
+    }
+
+    /// Unsigned, `self` isn't the minimum
+    #[inline(always)]
+    pub fn gt_mask(self, other: Self) -> Self {
+        self.min(other).eq_mask(self) ^ Self::broadcast_with(u8::MAX)
+    }
+
+    /// High bit of the lane i in the bit i
+    #[inline(always)]
+    pub fn move_mask(self) -> u32 {
+        unsafe { _mm256_movemask_epi8(self.0) as u32 }
+    }
+}
+
+impl_op!(U8x32, Add, add, AddAssign, add_assign, _mm256_add_epi8);
+impl_op!(U8x32, Sub, sub, SubAssign, sub_assign, _mm256_sub_epi8);
+impl_op!(
+    U8x32,
+    BitAnd,
+    bitand,
+    BitAndAssign,
+    bitand_assign,
+    _mm256_and_si256
+);
+impl_op!(
+    U8x32,
+    BitOr,
+    bitor,
+    BitOrAssign,
+    bitor_assign,
+    _mm256_or_si256
+);
+impl_op!(
+    U8x32,
+    BitXor,
+    bitxor,
+    BitXorAssign,
+    bitxor_assign,
+    _mm256_xor_si256
+);
+
+#[test]
+fn new_u8x32() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    assert_eq!(U8x32::from_array(BYTES).to_array(), BYTES);
+    assert_eq!(U8x32::zero().to_array(), [0; 32]);
+    assert_eq!(U8x32::broadcast_with(200).to_array(), [200; 32]);
+    let mut rng = rand::thread_rng();
+    let _ = U8x32::rand(&mut rng);
+}
+
+#[test]
+fn ops_u8x32() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = U8x32::from_array(BYTES);
+    let b = U8x32::broadcast_with(100);
+    let expected = |f: fn(u8, u8) -> u8| BYTES.map(|x| f(x, 100));
+    assert_eq!((a + b).to_array(), expected(u8::wrapping_add));
+    assert_eq!((a - b).to_array(), expected(u8::wrapping_sub));
+    assert_eq!((a & b).to_array(), expected(|x, y| x & y));
+    assert_eq!((a | b).to_array(), expected(|x, y| x | y));
+    assert_eq!((a ^ b).to_array(), expected(|x, y| x ^ y));
+    assert_eq!(a.saturating_add(b).to_array(), expected(u8::saturating_add));
+    assert_eq!(a.saturating_sub(b).to_array(), expected(u8::saturating_sub));
+    assert_eq!(a.max(b).to_array(), expected(u8::max));
+    assert_eq!(a.min(b).to_array(), expected(u8::min));
+
+    let mut c = a;
+    c += b;
+    c -= b;
+    c ^= b;
+    c &= b;
+    c |= U8x32::broadcast_with(1);
+    assert_eq!(c.to_array()[16..20], [101, 1, 1, 101]);
+}
+
+#[test]
+fn sum_u8x32() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    assert_eq!(U8x32::broadcast_with(255).sum(), 32 * 255);
+    assert_eq!(
+        U8x32::from_array(BYTES).sum(),
+        BYTES.iter().map(|&x| x as u32).sum()
+    );
+}
+
+#[test]
+fn mask_u8x32() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let a = U8x32::from_array(BYTES);
+    let b = U8x32::broadcast_with(128);
+    assert_eq!(a.eq_mask(b).move_mask(), 0b1000 << 16);
+    assert_eq!(a.lt_mask(b).move_mask(), 0xff07_ffff);
+    assert_eq!(a.gt_mask(b).move_mask(), 0b1111_0000 << 16);
+    assert_eq!(a.move_mask(), 0b1111_1000 << 16);
+}
@@ -1,6 +1,218 @@
+/// Operator and its assignment from an intrinsic on the registers
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+macro_rules! impl_op {
+    ($t:ident, $op:ident, $f:ident, $op_assign:ident, $f_assign:ident, $intrinsic:ident) => {
+        impl $op for $t {
+            type Output = Self;
+            #[inline(always)]
+            fn $f(self, other: Self) -> Self {
+                Self(unsafe { $intrinsic(self.0, other.0) })
+            }
+        }
+
+        impl $op_assign for $t {
+            #[inline(always)]
+            fn $f_assign(&mut self, other: Self) {
+                self.0 = unsafe { $intrinsic(self.0, other.0) }
+            }
+        }
+    };
+}
+
+/// Slice helpers `$v` of `$t` from functions calling each other by name, like `dispatch!` in
+/// vfloat.rs. With a `$feature`, `$v` runs a copy compiled with it when the CPU has it and
+/// otherwise the same code on `scalar::$t`
+macro_rules! dispatch_wide {
+    (
+        $v:ident, $t:ident, $m:ident;
+        $($(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? $body:block)*
+    ) => {
+        mod $m {
+            use super::*;
+            $(
+                #[inline(always)]
+                pub fn $name($($arg: $ty),*) $(-> $ret)? $body
+            )*
+        }
+
+        impl $v {
+            $(
+                $(#[$attr])*
+                pub fn $name($($arg: $ty),*) $(-> $ret)? {
+                    $m::$name($($arg),*)
+                }
+            )*
+        }
+    };
+    (
+        $v:ident, $t:ident, $m:ident, $feature:tt;
+        $($(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? $body:block)*
+    ) => {
+        mod $m {
+            use super::*;
+
+            pub mod baseline {
+                use super::*;
+                $(
+                    #[inline(always)]
+                    pub fn $name($($arg: $ty),*) $(-> $ret)? $body
+                )*
+            }
+
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            pub mod wide {
+                use super::*;
+                $(
+                    #[target_feature(enable = $feature)]
+                    pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                        baseline::$name($($arg),*)
+                    }
+                )*
+            }
+
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            pub mod fallback {
+                use super::*;
+                use $crate::scalar::$t;
+                $(
+                    #[inline(always)]
+                    pub fn $name($($arg: $ty),*) $(-> $ret)? $body
+                )*
+            }
+        }
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        impl $v {
+            $(
+                $(#[$attr])*
+                pub fn $name($($arg: $ty),*) $(-> $ret)? {
+                    if is_x86_feature_detected!($feature) {
+                        unsafe { $m::wide::$name($($arg),*) }
+                    } else {
+                        $crate::Cast::cast($m::fallback::$name($($crate::Cast::cast($arg)),*))
+                    }
+                }
+            )*
+        }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        impl $v {
+            $(
+                $(#[$attr])*
+                pub fn $name($($arg: $ty),*) $(-> $ret)? {
+                    $m::baseline::$name($($arg),*)
+                }
+            )*
+        }
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod double;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[allow(clippy::too_many_arguments)]
 mod float;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod int;
+/// Portable backend with the same API, the default on other architectures
+///
+/// `rev_half` and `rsqrt_half` are exact here.
+pub mod scalar;
+mod vdouble;
 mod vfloat;
+mod vint;
 
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub use double::*;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub use float::*;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub use int::*;
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+pub use scalar::*;
+pub use vdouble::*;
 pub use vfloat::*;
+pub use vint::*;
+
+/// Moves a value between the register and the `scalar` layout of a vector, which are the same,
+/// for the `dispatch_wide!` fallback
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+trait Cast<T> {
+    fn cast(self) -> T;
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+macro_rules! impl_cast {
+    ($($t:ident),*) => {
+        $(
+            const _: () = assert!(
+                core::mem::size_of::<$t>() == core::mem::size_of::<scalar::$t>()
+                    && core::mem::align_of::<$t>() == core::mem::align_of::<scalar::$t>()
+            );
+
+            impl Cast<scalar::$t> for $t {
+                fn cast(self) -> scalar::$t {
+                    unsafe { core::mem::transmute(self) }
+                }
+            }
+
+            impl Cast<$t> for scalar::$t {
+                fn cast(self) -> $t {
+                    unsafe { core::mem::transmute(self) }
+                }
+            }
+
+            impl<'a> Cast<&'a [scalar::$t]> for &'a [$t] {
+                fn cast(self) -> &'a [scalar::$t] {
+                    unsafe { core::slice::from_raw_parts(self.as_ptr() as _, self.len()) }
+                }
+            }
+
+            impl<'a> Cast<&'a mut [scalar::$t]> for &'a mut [$t] {
+                fn cast(self) -> &'a mut [scalar::$t] {
+                    unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr() as _, self.len()) }
+                }
+            }
+
+            impl Cast<Vec<scalar::$t>> for Vec<$t> {
+                fn cast(self) -> Vec<scalar::$t> {
+                    let mut v = core::mem::ManuallyDrop::new(self);
+                    unsafe { Vec::from_raw_parts(v.as_mut_ptr() as _, v.len(), v.capacity()) }
+                }
+            }
+
+            impl Cast<Vec<$t>> for Vec<scalar::$t> {
+                fn cast(self) -> Vec<$t> {
+                    let mut v = core::mem::ManuallyDrop::new(self);
+                    unsafe { Vec::from_raw_parts(v.as_mut_ptr() as _, v.len(), v.capacity()) }
+                }
+            }
+        )*
+    };
+}
+
+/// Arguments and results which are the same on both sides
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+macro_rules! impl_cast_same {
+    ($($t:ty),*) => {
+        $(
+            impl Cast<$t> for $t {
+                fn cast(self) -> $t {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+impl_cast!(F64x4, I32x8, U8x32);
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+impl_cast_same!((), usize, u64, Vec<f64>, Vec<i32>, Vec<u8>);
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+impl<'a> Cast<&'a mut rand::rngs::ThreadRng> for &'a mut rand::rngs::ThreadRng {
+    fn cast(self) -> &'a mut rand::rngs::ThreadRng {
+        self
+    }
+}
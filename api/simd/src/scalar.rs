@@ -0,0 +1,697 @@
+use core::cmp::{Ordering, PartialOrd};
+use core::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div, DivAssign,
+    Mul, MulAssign, Sub, SubAssign,
+};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+
+/// Lane `MASK >> 2 * i & 3` of `a` for the lanes 0 and 1 and of `b` for the lanes 2 and 3
+#[inline(always)]
+fn shuffle4<const MASK: i32>(a: &[f32], b: &[f32]) -> [f32; 4] {
+    let lane = |i: i32| (MASK >> (2 * i) & 3) as usize;
+    [a[lane(0)], a[lane(1)], b[lane(2)], b[lane(3)]]
+}
+
+/// Constructors and conversions of `$n` lanes of `$e`
+macro_rules! impl_lanes {
+    ($t:ident, $e:ty, $n:expr) => {
+        impl $t {
+            #[inline(always)]
+            fn zip(self, other: Self, f: impl Fn($e, $e) -> $e) -> Self {
+                let mut out = self.0;
+                for (a, b) in out.iter_mut().zip(other.0) {
+                    *a = f(*a, b);
+                }
+                Self(out)
+            }
+
+            #[inline(always)]
+            pub fn from_array(a: [$e; $n]) -> Self {
+                Self(a)
+            }
+
+            #[inline(always)]
+            pub fn rand(rng: &mut ThreadRng) -> Self {
+                let mut a = [0 as $e; $n];
+                rng.fill(&mut a[..]);
+                Self(a)
+            }
+
+            #[inline(always)]
+            pub fn zero() -> Self {
+                Self([0 as $e; $n])
+            }
+
+            #[inline(always)]
+            pub fn broadcast_with(a: $e) -> Self {
+                Self([a; $n])
+            }
+
+            #[inline(always)]
+            pub fn to_array(self) -> [$e; $n] {
+                self.0
+            }
+
+            /// Second operand when a lane is NaN, like `maxps`
+            #[inline(always)]
+            pub fn max(self, other: Self) -> Self {
+                self.zip(other, |a, b| if a > b { a } else { b })
+            }
+
+            /// Second operand when a lane is NaN, like `minps`
+            #[inline(always)]
+            pub fn min(self, other: Self) -> Self {
+                self.zip(other, |a, b| if a < b { a } else { b })
+            }
+        }
+    };
+}
+
+/// Operator and its assignment, lane by lane
+macro_rules! impl_zip {
+    ($t:ident, $op:ident, $f:ident, $op_assign:ident, $f_assign:ident, $g:expr) => {
+        impl $op for $t {
+            type Output = Self;
+            #[inline(always)]
+            fn $f(self, other: Self) -> Self {
+                self.zip(other, $g)
+            }
+        }
+
+        impl $op_assign for $t {
+            #[inline(always)]
+            fn $f_assign(&mut self, other: Self) {
+                *self = self.zip(other, $g);
+            }
+        }
+    };
+}
+
+macro_rules! impl_f32 {
+    ($t:ident, $n:expr) => {
+        impl_lanes!($t, f32, $n);
+
+        impl $t {
+            /// Within each group of 4 lanes
+            #[inline(always)]
+            pub fn shuffle<const MASK: i32>(self, other: Self) -> Self {
+                let mut out = [0.; $n];
+                for (i, out) in out.chunks_exact_mut(4).enumerate() {
+                    let lanes = 4 * i..4 * i + 4;
+                    out.copy_from_slice(&shuffle4::<MASK>(&self.0[lanes.clone()], &other.0[lanes]));
+                }
+                Self(out)
+            }
+
+            /// Within each group of 4 lanes
+            #[inline(always)]
+            pub fn permute<const MASK: i32>(self) -> Self {
+                self.shuffle::<MASK>(self)
+            }
+
+            #[inline(always)]
+            pub fn splat0(self) -> Self {
+                self.permute::<0b00_00_00_00>()
+            }
+
+            #[inline(always)]
+            pub fn splat1(self) -> Self {
+                self.permute::<0b01_01_01_01>()
+            }
+
+            #[inline(always)]
+            pub fn splat2(self) -> Self {
+                self.permute::<0b10_10_10_10>()
+            }
+
+            #[inline(always)]
+            pub fn splat3(self) -> Self {
+                self.permute::<0b11_11_11_11>()
+            }
+
+            #[inline(always)]
+            pub fn rev_half(self) -> Self {
+                Self(self.0.map(|a| 1. / a))
+            }
+
+            #[inline(always)]
+            pub fn rev(self) -> Self {
+                let two = Self::broadcast_with(2.);
+                let x0 = self.rev_half();
+                x0 * (two - self * x0)
+            }
+
+            #[inline(always)]
+            pub fn rsqrt_half(self) -> Self {
+                Self(self.0.map(|a| 1. / a.sqrt()))
+            }
+
+            #[inline(always)]
+            pub fn rsqrt(self) -> Self {
+                let half = Self::broadcast_with(0.5);
+                let three = Self::broadcast_with(3.);
+                let x0 = self.rsqrt_half();
+                half * x0 * (three - self * x0 * x0)
+            }
+        }
+
+        impl_zip!($t, Add, add, AddAssign, add_assign, |a, b| a + b);
+        impl_zip!($t, Sub, sub, SubAssign, sub_assign, |a, b| a - b);
+        impl_zip!($t, Mul, mul, MulAssign, mul_assign, |a, b| a * b);
+        impl_zip!($t, Div, div, DivAssign, div_assign, |a, b| a / b);
+    };
+}
+
+// ----------------------------------------
+// F32x4
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+pub struct F32x4(pub [f32; 4]);
+
+impl_f32!(F32x4, 4);
+
+impl F32x4 {
+    #[inline(always)]
+    pub fn new(a0: f32, a1: f32, a2: f32, a3: f32) -> Self {
+        Self([a0, a1, a2, a3])
+    }
+
+    /// Horizontal sum in every lane
+    #[inline(always)]
+    pub fn sum(self) -> Self {
+        let p0 = self.permute::<0b10_11_00_01>();
+        let a0 = self + p0;
+        let p1 = a0.permute::<0b01_00_11_10>();
+        a0 + p1
+    }
+
+    #[inline(always)]
+    pub fn pack(self, other0: Self, other1: Self, other2: Self) -> F32x4 {
+        let s0 = self.shuffle::<0b11_10_01_00>(other0);
+        let s1 = other1.shuffle::<0b11_10_01_00>(other2);
+        s0.shuffle::<0b11_01_10_00>(s1)
+    }
+}
+
+/// Compares the first lane only, like `comieq`
+impl PartialEq<F32x4> for F32x4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[0] == other.0[0]
+    }
+}
+
+/// Greater when the first lane is greater, equal otherwise, like `comigt`
+impl PartialOrd for F32x4 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(((self.0[0] > other.0[0]) as i32).cmp(&0))
+    }
+}
+
+// ----------------------------------------
+// F32x8
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(32))]
+pub struct F32x8(pub [f32; 8]);
+
+impl_f32!(F32x8, 8);
+
+impl F32x8 {
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    pub fn new(a0: f32, a1: f32, a2: f32, a3: f32, a4: f32, a5: f32, a6: f32, a7: f32) -> Self {
+        Self([a0, a1, a2, a3, a4, a5, a6, a7])
+    }
+
+    /// self * a + b, fused
+    #[inline(always)]
+    pub fn madd(self, a: Self, b: Self) -> Self {
+        let mut out = self.0;
+        for ((x, a), b) in out.iter_mut().zip(a.0).zip(b.0) {
+            *x = x.mul_add(a, b);
+        }
+        Self(out)
+    }
+}
+
+/// Comparisons of `$e` lanes, `$set` where they hold and `$sign` for `move_mask`
+macro_rules! impl_masks {
+    ($t:ident, $e:ty, $set:expr, $sign:expr) => {
+        impl $t {
+            /// Every bit of a lane is set where the comparison holds
+            #[inline(always)]
+            pub fn eq_mask(self, other: Self) -> Self {
+                self.zip(other, |a, b| if a == b { $set } else { 0 as $e })
+            }
+
+            #[inline(always)]
+            pub fn lt_mask(self, other: Self) -> Self {
+                self.zip(other, |a, b| if a < b { $set } else { 0 as $e })
+            }
+
+            #[inline(always)]
+            pub fn gt_mask(self, other: Self) -> Self {
+                self.zip(other, |a, b| if a > b { $set } else { 0 as $e })
+            }
+
+            /// Sign bit of the lane i in the bit i
+            #[inline(always)]
+            pub fn move_mask(self) -> u32 {
+                let sign: fn($e) -> bool = $sign;
+                self.0
+                    .iter()
+                    .enumerate()
+                    .fold(0, |mask, (i, &a)| mask | (sign(a) as u32) << i)
+            }
+        }
+    };
+}
+
+macro_rules! impl_f64 {
+    ($t:ident, $n:expr) => {
+        impl_lanes!($t, f64, $n);
+        impl_masks!($t, f64, f64::from_bits(u64::MAX), f64::is_sign_negative);
+
+        impl $t {
+            #[inline(always)]
+            pub fn sqrt(self) -> Self {
+                Self(self.0.map(f64::sqrt))
+            }
+        }
+
+        impl_zip!($t, Add, add, AddAssign, add_assign, |a, b| a + b);
+        impl_zip!($t, Sub, sub, SubAssign, sub_assign, |a, b| a - b);
+        impl_zip!($t, Mul, mul, MulAssign, mul_assign, |a, b| a * b);
+        impl_zip!($t, Div, div, DivAssign, div_assign, |a, b| a / b);
+    };
+}
+
+/// The arithmetic wraps, like the registers
+macro_rules! impl_int {
+    ($t:ident, $e:ty, $n:expr) => {
+        impl_lanes!($t, $e, $n);
+
+        impl_zip!($t, Add, add, AddAssign, add_assign, <$e>::wrapping_add);
+        impl_zip!($t, Sub, sub, SubAssign, sub_assign, <$e>::wrapping_sub);
+        impl_zip!($t, BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a
+            & b);
+        impl_zip!($t, BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+        impl_zip!($t, BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a
+            ^ b);
+    };
+}
+
+macro_rules! impl_i32 {
+    ($t:ident, $n:expr) => {
+        impl_int!($t, i32, $n);
+        impl_masks!($t, i32, -1, |a| a < 0);
+
+        impl $t {
+            /// Horizontal sum in every lane
+            #[inline(always)]
+            pub fn sum(self) -> Self {
+                Self::broadcast_with(self.0.iter().fold(0, |s, &a| s.wrapping_add(a)))
+            }
+
+            /// Zero from 32
+            #[inline(always)]
+            pub fn shl<const N: i32>(self) -> Self {
+                Self(self.0.map(|a| a.checked_shl(N as u32).unwrap_or(0)))
+            }
+
+            /// Arithmetic, the sign fills the lane from 32
+            #[inline(always)]
+            pub fn shr<const N: i32>(self) -> Self {
+                Self(self.0.map(|a| a >> N.min(31)))
+            }
+        }
+
+        impl_zip!($t, Mul, mul, MulAssign, mul_assign, i32::wrapping_mul);
+    };
+}
+
+macro_rules! impl_u8 {
+    ($t:ident, $n:expr) => {
+        impl_int!($t, u8, $n);
+        impl_masks!($t, u8, u8::MAX, |a| a >= 0x80);
+
+        impl $t {
+            /// Horizontal sum, which doesn't fit a lane
+            #[inline(always)]
+            pub fn sum(self) -> u32 {
+                self.0.iter().map(|&a| a as u32).sum()
+            }
+
+            #[inline(always)]
+            pub fn saturating_add(self, other: Self) -> Self {
+                self.zip(other, u8::saturating_add)
+            }
+
+            #[inline(always)]
+            pub fn saturating_sub(self, other: Self) -> Self {
+                self.zip(other, u8::saturating_sub)
+            }
+        }
+    };
+}
+
+// ----------------------------------------
+// F64x2
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+pub struct F64x2(pub [f64; 2]);
+
+impl_f64!(F64x2, 2);
+
+impl F64x2 {
+    #[inline(always)]
+    pub fn new(a0: f64, a1: f64) -> Self {
+        Self([a0, a1])
+    }
+
+    /// Horizontal sum in every lane
+    #[inline(always)]
+    pub fn sum(self) -> Self {
+        Self::broadcast_with(self.0[0] + self.0[1])
+    }
+}
+
+// ----------------------------------------
+// F64x4
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(32))]
+pub struct F64x4(pub [f64; 4]);
+
+impl_f64!(F64x4, 4);
+
+impl F64x4 {
+    #[inline(always)]
+    pub fn new(a0: f64, a1: f64, a2: f64, a3: f64) -> Self {
+        Self([a0, a1, a2, a3])
+    }
+
+    /// Horizontal sum in every lane, (a0 + a1) + (a2 + a3)
+    #[inline(always)]
+    pub fn sum(self) -> Self {
+        let [a0, a1, a2, a3] = self.0;
+        Self::broadcast_with((a0 + a1) + (a2 + a3))
+    }
+}
+
+// ----------------------------------------
+// I32x4
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+pub struct I32x4(pub [i32; 4]);
+
+impl_i32!(I32x4, 4);
+
+impl I32x4 {
+    #[inline(always)]
+    pub fn new(a0: i32, a1: i32, a2: i32, a3: i32) -> Self {
+        Self([a0, a1, a2, a3])
+    }
+}
+
+// ----------------------------------------
+// I32x8
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(32))]
+pub struct I32x8(pub [i32; 8]);
+
+impl_i32!(I32x8, 8);
+
+impl I32x8 {
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    pub fn new(a0: i32, a1: i32, a2: i32, a3: i32, a4: i32, a5: i32, a6: i32, a7: i32) -> Self {
+        Self([a0, a1, a2, a3, a4, a5, a6, a7])
+    }
+}
+
+// ----------------------------------------
+// U8x16
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+pub struct U8x16(pub [u8; 16]);
+
+impl_u8!(U8x16, 16);
+
+// ----------------------------------------
+// U8x32
+// ----------------------------------------
+
+#[derive(Clone, Copy)]
+#[repr(C, align(32))]
+pub struct U8x32(pub [u8; 32]);
+
+impl_u8!(U8x32, 32);
+
+#[test]
+fn scalar() {
+    let a = F32x4::new(0., 1., 2., 3.);
+    let b = F32x4::new(4., 5., 6., 7.);
+    assert_eq!(a.shuffle::<0b11_10_01_00>(b).to_array(), [0., 1., 6., 7.]);
+    assert_eq!(a.permute::<0b00_00_00_01>().to_array(), [1., 0., 0., 0.]);
+    assert_eq!(a.sum().to_array(), [6.; 4]);
+    assert_eq!(a.pack(b, a, b).to_array(), [0., 6., 1., 7.]);
+    assert_eq!(core::mem::align_of::<F32x4>(), 16);
+
+    let a = F32x8::new(0., 1., 2., 3., 4., 5., 6., 7.);
+    assert_eq!(a.splat1().to_array(), [1., 1., 1., 1., 5., 5., 5., 5.]);
+    assert_eq!(
+        a.madd(a, a).to_array(),
+        [0., 2., 6., 12., 20., 30., 42., 56.]
+    );
+    assert_eq!(core::mem::align_of::<F32x8>(), 32);
+
+    let a = F64x4::new(1., -2., 3., f64::NAN);
+    assert!(a.sum().to_array()[0].is_nan());
+    assert_eq!(a.gt_mask(F64x4::zero()).move_mask(), 0b0101);
+    let a = I32x4::new(i32::MAX, -8, 3, 0);
+    assert_eq!(
+        (a + I32x4::broadcast_with(1)).to_array(),
+        [i32::MIN, -7, 4, 1]
+    );
+    assert_eq!(a.shr::<2>().to_array(), [i32::MAX >> 2, -2, 0, 0]);
+    assert_eq!(a.shl::<32>().to_array(), [0; 4]);
+    assert_eq!(a.shr::<40>().to_array(), [0, -1, 0, 0]);
+    let a = U8x16::from_array([200; 16]);
+    assert_eq!((a + a).to_array(), [144; 16]);
+    assert_eq!(a.saturating_add(a).to_array(), [255; 16]);
+    assert_eq!(a.sum(), 3200);
+    assert_eq!(core::mem::align_of::<U8x32>(), 32);
+}
+
+/// Every operation of both backends on the same random values
+#[cfg(all(test, any(target_arch = "x86", target_arch = "x86_64")))]
+#[test]
+fn backends() {
+    let mut rng = rand::thread_rng();
+    let close = |a: &[f32], b: &[f32], tolerance: f32| {
+        a.iter()
+            .zip(b)
+            .all(|(a, b)| (a - b).abs() <= tolerance * a.abs().max(1.))
+    };
+
+    for _ in 0..100 {
+        let a = F32x4::rand(&mut rng) + F32x4::broadcast_with(0.1);
+        let b = F32x4::rand(&mut rng) + F32x4::broadcast_with(0.1);
+        let (x, y) = (
+            crate::F32x4::from_array(a.to_array()),
+            crate::F32x4::from_array(b.to_array()),
+        );
+        assert_eq!((a + b).to_array(), (x + y).to_array());
+        assert_eq!((a - b).to_array(), (x - y).to_array());
+        assert_eq!((a * b).to_array(), (x * y).to_array());
+        assert_eq!((a / b).to_array(), (x / y).to_array());
+        assert_eq!(a.max(b).to_array(), x.max(y).to_array());
+        assert_eq!(a.min(b).to_array(), x.min(y).to_array());
+        assert_eq!(
+            a.shuffle::<0b01_11_10_00>(b).to_array(),
+            x.shuffle::<0b01_11_10_00>(y).to_array()
+        );
+        assert_eq!(
+            a.permute::<0b00_10_11_01>().to_array(),
+            x.permute::<0b00_10_11_01>().to_array()
+        );
+        assert_eq!(a.splat2().to_array(), x.splat2().to_array());
+        assert_eq!(a.sum().to_array(), x.sum().to_array());
+        assert_eq!(a.pack(b, b, a).to_array(), x.pack(y, y, x).to_array());
+        assert_eq!(a == b, x == y);
+        assert_eq!(a.partial_cmp(&b), x.partial_cmp(&y));
+        assert!(close(
+            &a.rev_half().to_array(),
+            &x.rev_half().to_array(),
+            1e-3
+        ));
+        assert!(close(
+            &a.rsqrt_half().to_array(),
+            &x.rsqrt_half().to_array(),
+            1e-3
+        ));
+        assert!(close(&a.rev().to_array(), &x.rev().to_array(), 1e-6));
+        assert!(close(&a.rsqrt().to_array(), &x.rsqrt().to_array(), 1e-6));
+    }
+
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    for _ in 0..100 {
+        let a = F32x8::rand(&mut rng) + F32x8::broadcast_with(0.1);
+        let b = F32x8::rand(&mut rng) + F32x8::broadcast_with(0.1);
+        let (x, y) = (
+            crate::F32x8::from_array(a.to_array()),
+            crate::F32x8::from_array(b.to_array()),
+        );
+        assert_eq!((a + b).to_array(), (x + y).to_array());
+        assert_eq!((a - b).to_array(), (x - y).to_array());
+        assert_eq!((a * b).to_array(), (x * y).to_array());
+        assert_eq!((a / b).to_array(), (x / y).to_array());
+        assert_eq!(a.max(b).to_array(), x.max(y).to_array());
+        assert_eq!(a.min(b).to_array(), x.min(y).to_array());
+        assert_eq!(
+            a.shuffle::<0b01_11_10_00>(b).to_array(),
+            x.shuffle::<0b01_11_10_00>(y).to_array()
+        );
+        assert_eq!(
+            a.permute::<0b00_10_11_01>().to_array(),
+            x.permute::<0b00_10_11_01>().to_array()
+        );
+        assert_eq!(a.splat3().to_array(), x.splat3().to_array());
+        assert!(close(
+            &a.rev_half().to_array(),
+            &x.rev_half().to_array(),
+            1e-3
+        ));
+        assert!(close(
+            &a.rsqrt_half().to_array(),
+            &x.rsqrt_half().to_array(),
+            1e-3
+        ));
+        assert!(close(&a.rev().to_array(), &x.rev().to_array(), 1e-6));
+        assert!(close(&a.rsqrt().to_array(), &x.rsqrt().to_array(), 1e-6));
+        if is_x86_feature_detected!("fma") {
+            assert_eq!(a.madd(b, a).to_array(), x.madd(y, x).to_array());
+        }
+    }
+}
+
+/// The new types of both backends on the same random values, bit for bit
+#[cfg(all(test, any(target_arch = "x86", target_arch = "x86_64")))]
+#[test]
+fn backends_double_int() {
+    let mut rng = rand::thread_rng();
+    let bits = |a: &[f64]| a.iter().map(|a| a.to_bits()).collect::<Vec<_>>();
+    let (avx, avx2) = (
+        is_x86_feature_detected!("avx"),
+        is_x86_feature_detected!("avx2"),
+    );
+
+    macro_rules! check_f64 {
+        ($t:ident) => {
+            let mut a = $t::rand(&mut rng) - $t::broadcast_with(0.5);
+            let b = $t::rand(&mut rng) - $t::broadcast_with(0.5);
+            a.0[0] = b.0[0];
+            let (x, y) = (
+                crate::$t::from_array(a.to_array()),
+                crate::$t::from_array(b.to_array()),
+            );
+            assert_eq!(bits(&(a + b).to_array()), bits(&(x + y).to_array()));
+            assert_eq!(bits(&(a - b).to_array()), bits(&(x - y).to_array()));
+            assert_eq!(bits(&(a * b).to_array()), bits(&(x * y).to_array()));
+            assert_eq!(bits(&(a / b).to_array()), bits(&(x / y).to_array()));
+            assert_eq!(bits(&a.max(b).to_array()), bits(&x.max(y).to_array()));
+            assert_eq!(bits(&a.min(b).to_array()), bits(&x.min(y).to_array()));
+            assert_eq!(bits(&a.sum().to_array()), bits(&x.sum().to_array()));
+            assert_eq!(bits(&a.sqrt().to_array()), bits(&x.sqrt().to_array()));
+            for (m, n) in [
+                (a.eq_mask(b), x.eq_mask(y)),
+                (a.lt_mask(b), x.lt_mask(y)),
+                (a.gt_mask(b), x.gt_mask(y)),
+            ] {
+                assert_eq!(bits(&m.to_array()), bits(&n.to_array()));
+                assert_eq!(m.move_mask(), n.move_mask());
+            }
+            assert_eq!(a.move_mask(), x.move_mask());
+        };
+    }
+
+    macro_rules! check_int {
+        ($t:ident) => {{
+            let mut a = $t::rand(&mut rng);
+            let b = $t::rand(&mut rng);
+            a.0[0] = b.0[0];
+            let (x, y) = (
+                crate::$t::from_array(a.to_array()),
+                crate::$t::from_array(b.to_array()),
+            );
+            assert_eq!((a + b).to_array(), (x + y).to_array());
+            assert_eq!((a - b).to_array(), (x - y).to_array());
+            assert_eq!((a & b).to_array(), (x & y).to_array());
+            assert_eq!((a | b).to_array(), (x | y).to_array());
+            assert_eq!((a ^ b).to_array(), (x ^ y).to_array());
+            assert_eq!(a.max(b).to_array(), x.max(y).to_array());
+            assert_eq!(a.min(b).to_array(), x.min(y).to_array());
+            assert_eq!(a.eq_mask(b).to_array(), x.eq_mask(y).to_array());
+            assert_eq!(a.lt_mask(b).to_array(), x.lt_mask(y).to_array());
+            assert_eq!(a.gt_mask(b).to_array(), x.gt_mask(y).to_array());
+            assert_eq!(a.move_mask(), x.move_mask());
+            (a, b, x, y)
+        }};
+    }
+
+    macro_rules! check_i32 {
+        ($t:ident) => {
+            let (a, b, x, y) = check_int!($t);
+            assert_eq!((a * b).to_array(), (x * y).to_array());
+            assert_eq!(a.sum().to_array(), x.sum().to_array());
+            assert_eq!(a.shl::<7>().to_array(), x.shl::<7>().to_array());
+            assert_eq!(a.shl::<32>().to_array(), x.shl::<32>().to_array());
+            assert_eq!(a.shr::<7>().to_array(), x.shr::<7>().to_array());
+            assert_eq!(a.shr::<33>().to_array(), x.shr::<33>().to_array());
+        };
+    }
+
+    macro_rules! check_u8 {
+        ($t:ident) => {
+            let (a, b, x, y) = check_int!($t);
+            assert_eq!(a.sum(), x.sum());
+            assert_eq!(
+                a.saturating_add(b).to_array(),
+                x.saturating_add(y).to_array()
+            );
+            assert_eq!(
+                a.saturating_sub(b).to_array(),
+                x.saturating_sub(y).to_array()
+            );
+        };
+    }
+
+    for _ in 0..100 {
+        check_f64!(F64x2);
+        check_i32!(I32x4);
+        check_u8!(U8x16);
+        if avx {
+            check_f64!(F64x4);
+        }
+        if avx2 {
+            check_i32!(I32x8);
+            check_u8!(U8x32);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use crate::{F64x2, F64x4};
+use rand::rngs::ThreadRng;
+
+#[cfg(test)]
+const EPSILON: f64 = 1e-12;
+
+/// Slice helpers of `$t` in the module `$m`, dispatched on `$feature` if any
+macro_rules! impl_vf64 {
+    ($v:ident, $t:ident, $m:ident $(, $feature:tt)?) => {
+        dispatch_wide! {
+            $v, $t, $m $(, $feature)?;
+
+            pub fn alloc(n: usize) -> Vec<$t> {
+                vec![$t::zero(); n]
+            }
+
+            pub fn rand(n: usize, rng: &mut ThreadRng) -> Vec<$t> {
+                let mut v = alloc(n);
+                randomize(&mut v, rng);
+                v
+            }
+
+            pub fn randomize(v: &mut [$t], rng: &mut ThreadRng) {
+                for a in v.iter_mut() {
+                    *a = $t::rand(rng);
+                }
+            }
+
+            pub fn broadcast_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a = b;
+                }
+            }
+
+            pub fn into_vf(v: Vec<$t>) -> Vec<f64> {
+                v.into_iter().flat_map($t::to_array).collect()
+            }
+
+            pub fn add_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a += b;
+                }
+            }
+
+            pub fn add_assign_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a += b;
+                }
+            }
+
+            pub fn sub_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a -= b;
+                }
+            }
+
+            pub fn sub_assign_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a -= b;
+                }
+            }
+
+            pub fn mul_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a *= b;
+                }
+            }
+
+            pub fn mul_assign_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a *= b;
+                }
+            }
+
+            pub fn div_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a /= b;
+                }
+            }
+
+            pub fn div_assign_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a /= b;
+                }
+            }
+
+            pub fn dot(v0: &[$t], v1: &[$t]) -> $t {
+                let mut c = $t::zero();
+                for (&a, &b) in v0.iter().zip(v1.iter()) {
+                    c += a * b;
+                }
+                c.sum()
+            }
+
+            pub fn sum(v: &[$t]) -> $t {
+                let mut c = $t::zero();
+                for &a in v.iter() {
+                    c += a;
+                }
+                c.sum()
+            }
+
+            /// Exact, unlike `VF32x4::rnorm`
+            pub fn rnorm(v: &[$t]) -> $t {
+                $t::broadcast_with(1.) / dot(v, v).sqrt()
+            }
+
+            pub fn normalize(v: &mut [$t]) {
+                let n = rnorm(v);
+                mul_assign_with(v, n);
+            }
+        }
+    };
+}
+
+// ----------------------------------------
+// VF64x2
+// ----------------------------------------
+
+pub struct VF64x2;
+
+impl_vf64!(VF64x2, F64x2, f64x2);
+
+// ----------------------------------------
+// VF64x4
+// ----------------------------------------
+
+/// Runs with AVX when the CPU has it and on `scalar::F64x4` otherwise
+pub struct VF64x4;
+
+impl_vf64!(VF64x4, F64x4, f64x4, "avx");
+
+#[test]
+fn new_f64x2() {
+    let mut rng = rand::thread_rng();
+    assert_eq!(VF64x2::rand(3, &mut rng).len(), 3);
+
+    let mut v = VF64x2::alloc(2);
+    v[0] = F64x2::new(0., 1.);
+    v[1] = F64x2::new(2., 3.);
+    assert_eq!(&VF64x2::into_vf(v), &[0., 1., 2., 3.]);
+}
+
+#[test]
+fn ops_f64x2() {
+    let mut v0 = vec![F64x2::new(0., 1.), F64x2::new(2., 3.)];
+    let v1 = vec![F64x2::new(1., 2.), F64x2::new(4., 8.)];
+
+    VF64x2::add_assign(&mut v0, &v1);
+    VF64x2::mul_assign_with(&mut v0, F64x2::broadcast_with(2.));
+    VF64x2::div_assign(&mut v0, &v1);
+    VF64x2::sub_assign_with(&mut v0, F64x2::broadcast_with(1.));
+    assert_eq!(&VF64x2::into_vf(v0), &[1., 2., 2., 1.75]);
+
+    let v = [F64x2::new(0., 1.), F64x2::new(2., 3.)];
+    assert_eq!(VF64x2::dot(&v, &v1).to_array(), [34.; 2]);
+    assert_eq!(VF64x2::sum(&v).to_array(), [6.; 2]);
+}
+
+#[test]
+fn normalize_f64x2() {
+    let mut v = vec![F64x2::new(3., 0.), F64x2::new(0., 4.)];
+    assert_eq!(VF64x2::rnorm(&v).to_array(), [0.2; 2]);
+
+    VF64x2::normalize(&mut v);
+    for x in VF64x2::dot(&v, &v).to_array().iter() {
+        assert!((x - 1.).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn ops_f64x4() {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    let mut v0 = vec![F64x4::new(0., 1., 2., 3.), F64x4::new(4., 5., 6., 7.)];
+    let v1 = vec![F64x4::broadcast_with(2.); 2];
+
+    VF64x4::add_assign(&mut v0, &v1);
+    VF64x4::sub_assign(&mut v0, &v1);
+    VF64x4::mul_assign(&mut v0, &v1);
+    VF64x4::div_assign_with(&mut v0, F64x4::broadcast_with(4.));
+    assert_eq!(
+        &VF64x4::into_vf(v0.clone()),
+        &[0., 0.5, 1., 1.5, 2., 2.5, 3., 3.5]
+    );
+    assert_eq!(VF64x4::sum(&v0).to_array(), [14.; 4]);
+    assert_eq!(VF64x4::dot(&v0, &v1).to_array(), [28.; 4]);
+
+    let mut v = vec![F64x4::new(3., 0., 0., 0.), F64x4::new(0., 0., 4., 0.)];
+    VF64x4::normalize(&mut v);
+    let expected = [0.6, 0., 0., 0., 0., 0., 0.8, 0.];
+    for (x, y) in VF64x4::into_vf(v).iter().zip(expected) {
+        assert!((x - y).abs() < EPSILON);
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[test]
+fn backends_f64x4() {
+    use crate::{scalar, Cast};
+
+    if !is_x86_feature_detected!("avx") {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let v0 = VF64x4::rand(9, &mut rng);
+    let v1 = VF64x4::rand(9, &mut rng);
+    let b = F64x4::rand(&mut rng);
+    let bits = |v: Vec<f64>| v.into_iter().map(f64::to_bits).collect::<Vec<_>>();
+    let s1: &[scalar::F64x4] = v1.as_slice().cast();
+    let sb: scalar::F64x4 = b.cast();
+
+    type Binary<T> = fn(&mut [T], &[T]);
+    type WithScalar<T> = fn(&mut [T], T);
+
+    // Dispatched against the scalar fallback
+    let ops: [(Binary<F64x4>, Binary<scalar::F64x4>); 4] = [
+        (VF64x4::add_assign, f64x4::fallback::add_assign),
+        (VF64x4::sub_assign, f64x4::fallback::sub_assign),
+        (VF64x4::mul_assign, f64x4::fallback::mul_assign),
+        (VF64x4::div_assign, f64x4::fallback::div_assign),
+    ];
+    for (dispatched, fallback) in ops {
+        let (mut a, mut c): (_, Vec<scalar::F64x4>) = (v0.clone(), v0.clone().cast());
+        dispatched(&mut a, &v1);
+        fallback(&mut c, s1);
+        assert_eq!(bits(VF64x4::into_vf(a)), bits(f64x4::fallback::into_vf(c)));
+    }
+    let ops: [(WithScalar<F64x4>, WithScalar<scalar::F64x4>); 4] = [
+        (VF64x4::add_assign_with, f64x4::fallback::add_assign_with),
+        (VF64x4::sub_assign_with, f64x4::fallback::sub_assign_with),
+        (VF64x4::mul_assign_with, f64x4::fallback::mul_assign_with),
+        (VF64x4::div_assign_with, f64x4::fallback::div_assign_with),
+    ];
+    for (dispatched, fallback) in ops {
+        let (mut a, mut c): (_, Vec<scalar::F64x4>) = (v0.clone(), v0.clone().cast());
+        dispatched(&mut a, b);
+        fallback(&mut c, sb);
+        assert_eq!(bits(VF64x4::into_vf(a)), bits(f64x4::fallback::into_vf(c)));
+    }
+    let (mut a, mut c): (_, Vec<scalar::F64x4>) = (v0.clone(), v0.clone().cast());
+    VF64x4::normalize(&mut a);
+    f64x4::fallback::normalize(&mut c);
+    assert_eq!(bits(VF64x4::into_vf(a)), bits(f64x4::fallback::into_vf(c)));
+
+    let s0: &[scalar::F64x4] = v0.as_slice().cast();
+    let lanes = |a: F64x4| bits(a.to_array().to_vec());
+    let scalar_lanes = |a: scalar::F64x4| bits(a.to_array().to_vec());
+    assert_eq!(
+        lanes(VF64x4::dot(&v0, &v1)),
+        scalar_lanes(f64x4::fallback::dot(s0, s1))
+    );
+    assert_eq!(
+        lanes(VF64x4::sum(&v0)),
+        scalar_lanes(f64x4::fallback::sum(s0))
+    );
+    assert_eq!(
+        lanes(VF64x4::rnorm(&v0)),
+        scalar_lanes(f64x4::fallback::rnorm(s0))
+    );
+    assert_eq!(f64x4::fallback::alloc(3).len(), 3);
+}
@@ -1,4 +1,4 @@
-use crate::float::F32x4;
+use crate::F32x4;
 use rand::rngs::ThreadRng;
 
 #[cfg(test)]
@@ -42,7 +42,46 @@ impl VF32x4 {
         let cap = v.capacity() * 4;
         unsafe { Vec::from_raw_parts(ptr, len, cap) }
     }
+}
+
+/// Each function is compiled twice: `baseline` for any CPU and `avx` with AVX enabled, which
+/// `VF32x4` calls when the CPU has it
+macro_rules! dispatch {
+    ($(pub fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? $body:block)*) => {
+        mod baseline {
+            use super::*;
+            $(
+                #[inline(always)]
+                pub fn $name($($arg: $ty),*) $(-> $ret)? $body
+            )*
+        }
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        mod avx {
+            use super::*;
+            $(
+                #[target_feature(enable = "avx")]
+                pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                    baseline::$name($($arg),*)
+                }
+            )*
+        }
+
+        impl VF32x4 {
+            $(
+                pub fn $name($($arg: $ty),*) $(-> $ret)? {
+                    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                    if is_x86_feature_detected!("avx") {
+                        return unsafe { avx::$name($($arg),*) };
+                    }
+                    baseline::$name($($arg),*)
+                }
+            )*
+        }
+    };
+}
 
+dispatch! {
     pub fn add_assign(v0: &mut [F32x4], v1: &[F32x4]) {
         for (a, &b) in v0.iter_mut().zip(v1.iter()) {
             *a += b;
@@ -128,7 +167,7 @@ impl VF32x4 {
     }
 
     pub fn normalize(v: &mut [F32x4]) {
-        let n = Self::rnorm(v);
+        let n = rnorm(v);
         for a in v.iter_mut() {
             *a *= n;
         }
@@ -289,3 +328,64 @@ fn normalize() {
         assert!(x.abs() < EPSILON);
     }
 }
+
+#[test]
+fn backends() {
+    let mut rng = rand::thread_rng();
+    let v0 = VF32x4::rand(9, &mut rng);
+    let v1 = VF32x4::rand(9, &mut rng);
+    let b = F32x4::rand(&mut rng);
+    let bits = |v: &[F32x4]| {
+        v.iter()
+            .flat_map(|a| a.to_array())
+            .map(f32::to_bits)
+            .collect::<Vec<_>>()
+    };
+
+    type Binary = fn(&mut [F32x4], &[F32x4]);
+    type WithScalar = fn(&mut [F32x4], F32x4);
+    type Unary = fn(&mut [F32x4]);
+
+    // Dispatched against baseline
+    let ops: [(Binary, Binary); 4] = [
+        (VF32x4::add_assign, baseline::add_assign),
+        (VF32x4::sub_assign, baseline::sub_assign),
+        (VF32x4::mul_assign, baseline::mul_assign),
+        (VF32x4::div_assign, baseline::div_assign),
+    ];
+    for (dispatched, base) in ops {
+        let (mut a, mut c) = (v0.clone(), v0.clone());
+        dispatched(&mut a, &v1);
+        base(&mut c, &v1);
+        assert_eq!(bits(&a), bits(&c));
+    }
+    let ops: [(WithScalar, WithScalar); 4] = [
+        (VF32x4::add_assign_with, baseline::add_assign_with),
+        (VF32x4::sub_assign_with, baseline::sub_assign_with),
+        (VF32x4::mul_assign_with, baseline::mul_assign_with),
+        (VF32x4::div_assign_with, baseline::div_assign_with),
+    ];
+    for (dispatched, base) in ops {
+        let (mut a, mut c) = (v0.clone(), v0.clone());
+        dispatched(&mut a, b);
+        base(&mut c, b);
+        assert_eq!(bits(&a), bits(&c));
+    }
+    let ops: [(Unary, Unary); 3] = [
+        (VF32x4::rev_half, baseline::rev_half),
+        (VF32x4::rev, baseline::rev),
+        (VF32x4::normalize, baseline::normalize),
+    ];
+    for (dispatched, base) in ops {
+        let (mut a, mut c) = (v0.clone(), v0.clone());
+        dispatched(&mut a);
+        base(&mut c);
+        assert_eq!(bits(&a), bits(&c));
+    }
+    assert_eq!(
+        bits(&[VF32x4::dot(&v0, &v1)]),
+        bits(&[baseline::dot(&v0, &v1)])
+    );
+    assert_eq!(bits(&[VF32x4::sum(&v0)]), bits(&[baseline::sum(&v0)]));
+    assert_eq!(bits(&[VF32x4::rnorm(&v0)]), bits(&[baseline::rnorm(&v0)]));
+}
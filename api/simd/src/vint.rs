@@ -0,0 +1,300 @@
+use crate::{I32x4, I32x8, U8x16, U8x32};
+use rand::rngs::ThreadRng;
+
+/// Slice helpers of `$t` in the module `$m`, dispatched on `$feature` if any, with the `$extra`
+/// ones of its lanes; the arithmetic wraps
+macro_rules! impl_vint {
+    ($v:ident, $t:ident, $e:ty, $into:ident, $m:ident $(, $feature:tt)?; $($extra:tt)*) => {
+        dispatch_wide! {
+            $v, $t, $m $(, $feature)?;
+
+            pub fn alloc(n: usize) -> Vec<$t> {
+                vec![$t::zero(); n]
+            }
+
+            pub fn rand(n: usize, rng: &mut ThreadRng) -> Vec<$t> {
+                let mut v = alloc(n);
+                randomize(&mut v, rng);
+                v
+            }
+
+            pub fn randomize(v: &mut [$t], rng: &mut ThreadRng) {
+                for a in v.iter_mut() {
+                    *a = $t::rand(rng);
+                }
+            }
+
+            pub fn broadcast_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a = b;
+                }
+            }
+
+            pub fn $into(v: Vec<$t>) -> Vec<$e> {
+                v.into_iter().flat_map($t::to_array).collect()
+            }
+
+            pub fn add_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a += b;
+                }
+            }
+
+            pub fn add_assign_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a += b;
+                }
+            }
+
+            pub fn sub_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a -= b;
+                }
+            }
+
+            pub fn sub_assign_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a -= b;
+                }
+            }
+
+            pub fn and_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a &= b;
+                }
+            }
+
+            pub fn or_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a |= b;
+                }
+            }
+
+            pub fn xor_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a ^= b;
+                }
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+macro_rules! impl_vi32 {
+    ($v:ident, $t:ident, $m:ident $(, $feature:tt)?) => {
+        impl_vint! {
+            $v, $t, i32, into_vi, $m $(, $feature)?;
+
+            pub fn mul_assign(v0: &mut [$t], v1: &[$t]) {
+                for (a, &b) in v0.iter_mut().zip(v1.iter()) {
+                    *a *= b;
+                }
+            }
+
+            pub fn mul_assign_with(v: &mut [$t], b: $t) {
+                for a in v.iter_mut() {
+                    *a *= b;
+                }
+            }
+
+            pub fn dot(v0: &[$t], v1: &[$t]) -> $t {
+                let mut c = $t::zero();
+                for (&a, &b) in v0.iter().zip(v1.iter()) {
+                    c += a * b;
+                }
+                c.sum()
+            }
+
+            pub fn sum(v: &[$t]) -> $t {
+                let mut c = $t::zero();
+                for &a in v.iter() {
+                    c += a;
+                }
+                c.sum()
+            }
+        }
+    };
+}
+
+macro_rules! impl_vu8 {
+    ($v:ident, $t:ident, $m:ident $(, $feature:tt)?) => {
+        impl_vint! {
+            $v, $t, u8, into_vu, $m $(, $feature)?;
+
+            /// Doesn't wrap
+            pub fn sum(v: &[$t]) -> u64 {
+                v.iter().map(|a| a.sum() as u64).sum()
+            }
+        }
+    };
+}
+
+// ----------------------------------------
+// VI32x4
+// ----------------------------------------
+
+pub struct VI32x4;
+
+impl_vi32!(VI32x4, I32x4, i32x4);
+
+// ----------------------------------------
+// VI32x8
+// ----------------------------------------
+
+/// Runs with AVX2 when the CPU has it and on `scalar::I32x8` otherwise
+pub struct VI32x8;
+
+impl_vi32!(VI32x8, I32x8, i32x8, "avx2");
+
+// ----------------------------------------
+// VU8x16
+// ----------------------------------------
+
+pub struct VU8x16;
+
+impl_vu8!(VU8x16, U8x16, u8x16);
+
+// ----------------------------------------
+// VU8x32
+// ----------------------------------------
+
+/// Runs with AVX2 when the CPU has it and on `scalar::U8x32` otherwise
+pub struct VU8x32;
+
+impl_vu8!(VU8x32, U8x32, u8x32, "avx2");
+
+#[test]
+fn new_i32x4() {
+    let mut rng = rand::thread_rng();
+    assert_eq!(VI32x4::rand(3, &mut rng).len(), 3);
+
+    let mut v = VI32x4::alloc(2);
+    v[0] = I32x4::new(0, 1, 2, 3);
+    v[1] = I32x4::new(4, 5, 6, 7);
+    assert_eq!(&VI32x4::into_vi(v), &[0, 1, 2, 3, 4, 5, 6, 7]);
+}
+
+#[test]
+fn ops_i32x4() {
+    let mut v0 = vec![I32x4::new(0, 1, 2, 3), I32x4::new(4, 5, 6, i32::MAX)];
+    let v1 = vec![I32x4::new(1, 2, 3, 4), I32x4::broadcast_with(1)];
+
+    VI32x4::add_assign(&mut v0, &v1);
+    VI32x4::mul_assign_with(&mut v0, I32x4::broadcast_with(3));
+    VI32x4::sub_assign(&mut v0, &v1);
+    VI32x4::xor_assign(&mut v0, &v1);
+    assert_eq!(
+        &VI32x4::into_vi(v0),
+        &[3, 5, 15, 21, 15, 16, 21, i32::MAX - 1]
+    );
+
+    let v = [I32x4::new(0, 1, 2, 3), I32x4::new(4, 5, 6, 7)];
+    assert_eq!(VI32x4::dot(&v, &v1).to_array(), [42; 4]);
+    assert_eq!(VI32x4::sum(&v).to_array(), [28; 4]);
+}
+
+#[test]
+fn ops_u8x16() {
+    let mut v0 = vec![U8x16::broadcast_with(200), U8x16::broadcast_with(3)];
+    let v1 = vec![U8x16::broadcast_with(100), U8x16::broadcast_with(5)];
+
+    VU8x16::add_assign(&mut v0, &v1);
+    assert_eq!(VU8x16::sum(&v0), 16 * 44 + 16 * 8);
+    VU8x16::and_assign(&mut v0, &v1);
+    VU8x16::or_assign(&mut v0, &v1);
+    VU8x16::sub_assign_with(&mut v0, U8x16::broadcast_with(1));
+    let v = VU8x16::into_vu(v0);
+    assert_eq!(&v[..16], &[100 - 1; 16]);
+    assert_eq!(&v[16..], &[5 - 1; 16]);
+}
+
+#[test]
+fn ops_avx2() {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let mut v0 = vec![I32x8::new(0, 1, 2, 3, 4, 5, 6, 7); 2];
+    VI32x8::mul_assign(&mut v0, &[I32x8::broadcast_with(2); 2]);
+    VI32x8::sub_assign_with(&mut v0, I32x8::broadcast_with(1));
+    assert_eq!(VI32x8::sum(&v0).to_array(), [2 * 48; 8]);
+    assert_eq!(VI32x8::dot(&v0, &v0).to_array(), [2 * 456; 8]);
+
+    let v = vec![U8x32::broadcast_with(255); 3];
+    assert_eq!(VU8x32::sum(&v), 3 * 32 * 255);
+    assert_eq!(VU8x32::into_vu(v).len(), 96);
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[test]
+fn backends_avx2() {
+    use crate::{scalar, Cast};
+
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let v0 = VI32x8::rand(9, &mut rng);
+    let v1 = VI32x8::rand(9, &mut rng);
+    let b = I32x8::rand(&mut rng);
+    let s1: &[scalar::I32x8] = v1.as_slice().cast();
+    let sb: scalar::I32x8 = b.cast();
+
+    type Binary<T> = fn(&mut [T], &[T]);
+    type WithScalar<T> = fn(&mut [T], T);
+
+    // Dispatched against the scalar fallback
+    let ops: [(Binary<I32x8>, Binary<scalar::I32x8>); 6] = [
+        (VI32x8::add_assign, i32x8::fallback::add_assign),
+        (VI32x8::sub_assign, i32x8::fallback::sub_assign),
+        (VI32x8::mul_assign, i32x8::fallback::mul_assign),
+        (VI32x8::and_assign, i32x8::fallback::and_assign),
+        (VI32x8::or_assign, i32x8::fallback::or_assign),
+        (VI32x8::xor_assign, i32x8::fallback::xor_assign),
+    ];
+    for (dispatched, fallback) in ops {
+        let (mut a, mut c): (_, Vec<scalar::I32x8>) = (v0.clone(), v0.clone().cast());
+        dispatched(&mut a, &v1);
+        fallback(&mut c, s1);
+        assert_eq!(VI32x8::into_vi(a), i32x8::fallback::into_vi(c));
+    }
+    let ops: [(WithScalar<I32x8>, WithScalar<scalar::I32x8>); 3] = [
+        (VI32x8::add_assign_with, i32x8::fallback::add_assign_with),
+        (VI32x8::sub_assign_with, i32x8::fallback::sub_assign_with),
+        (VI32x8::mul_assign_with, i32x8::fallback::mul_assign_with),
+    ];
+    for (dispatched, fallback) in ops {
+        let (mut a, mut c): (_, Vec<scalar::I32x8>) = (v0.clone(), v0.clone().cast());
+        dispatched(&mut a, b);
+        fallback(&mut c, sb);
+        assert_eq!(VI32x8::into_vi(a), i32x8::fallback::into_vi(c));
+    }
+    let s0: &[scalar::I32x8] = v0.as_slice().cast();
+    assert_eq!(
+        VI32x8::dot(&v0, &v1).to_array(),
+        i32x8::fallback::dot(s0, s1).to_array()
+    );
+    assert_eq!(
+        VI32x8::sum(&v0).to_array(),
+        i32x8::fallback::sum(s0).to_array()
+    );
+
+    let v0 = VU8x32::rand(9, &mut rng);
+    let v1 = VU8x32::rand(9, &mut rng);
+    let s1: &[scalar::U8x32] = v1.as_slice().cast();
+    let ops: [(Binary<U8x32>, Binary<scalar::U8x32>); 5] = [
+        (VU8x32::add_assign, u8x32::fallback::add_assign),
+        (VU8x32::sub_assign, u8x32::fallback::sub_assign),
+        (VU8x32::and_assign, u8x32::fallback::and_assign),
+        (VU8x32::or_assign, u8x32::fallback::or_assign),
+        (VU8x32::xor_assign, u8x32::fallback::xor_assign),
+    ];
+    for (dispatched, fallback) in ops {
+        let (mut a, mut c): (_, Vec<scalar::U8x32>) = (v0.clone(), v0.clone().cast());
+        dispatched(&mut a, &v1);
+        fallback(&mut c, s1);
+        assert_eq!(VU8x32::into_vu(a), u8x32::fallback::into_vu(c));
+    }
+    assert_eq!(VU8x32::sum(&v0), u8x32::fallback::sum(v0.as_slice().cast()));
+}